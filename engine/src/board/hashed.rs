@@ -56,6 +56,10 @@ impl BoardInternal for HashBased {
         self.cells.remove(&(col, row));
     }
 
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
     fn get_iter<'a>(&'a self) -> Box<Iterator<Item=CellIterType> + 'a> {
         Box::new(IntoIterator::into_iter(self))
     }
@@ -10,9 +10,16 @@
 
 mod vect;
 mod hashed;
+mod packed;
+mod topology;
+pub mod transform;
 
 pub use self::hashed::HashBased as HashedBoard;
 pub use self::vect::SymVecBased as SymVecBoard;
+pub use self::packed::BitPacked as BitPackedBoard;
+pub use self::topology::{Grid, Topology, TopologyError};
+
+use core::Rule;
 
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
 pub struct Coord {
@@ -24,25 +31,45 @@ pub struct Coord {
 pub enum Cell {
     Empty,
     // occupied cell contains its generation
-    Occupied { gen: usize }
+    Occupied { gen: usize },
+    // cell of a Generations rule that is no longer alive, it passes through
+    // states 2, 3, ... and becomes empty after the last one, such cells
+    // are neither counted as neighbours nor included into population
+    Dying { state: usize },
+    // cell of a rule table in state 2 or above, state 1 is an occupied
+    // cell, such cells are counted into population as live ones
+    State { state: usize },
 }
 
 pub struct CellDesc {
     pub coord: Coord,
     pub gen: usize,
     pub is_alive: bool,
+    // 0 for empty cells, 1 for live ones and 2 or more for dying
+    // ones or cells of rule tables
+    pub state: usize,
     pub new_line: bool,
 }
 
 pub type CellIterType = (isize, isize, Cell);
 
-pub trait BoardInternal {
+pub trait BoardInternal: Send + Sync {
     fn get_cell(&self, col: isize, row: isize) -> Option<&Cell>;
     fn set_cell(&mut self, col: isize, row: isize, val: Cell);
     fn ensure_cell(&mut self, col: isize, row: isize);
     fn rm_cell(&mut self, col: isize, row: isize);
 
     fn get_iter<'a>(&'a self) -> Box<Iterator<Item=CellIterType> + 'a>;
+
+    // number of allocated cells, both live and empty ones
+    fn len(&self) -> usize;
+
+    // computes the whole next generation of an infinite board at once,
+    // returns new cells and their population, backends that
+    // can only be updated cell by cell return None
+    fn next_generation(&self, _rule: &Rule) -> Option<(Box<BoardInternal>, u64)> {
+        None
+    }
 }
 
 pub struct Board {
@@ -50,8 +77,8 @@ pub struct Board {
 
     population: u64,
 
-    rows: Option<usize>,
-    cols: Option<usize>,
+    // board sizes and the way its edges are joined
+    grid: Grid,
 }
 
 #[inline]
@@ -71,6 +98,15 @@ fn cycle(x: isize, min_val: isize, max_val: isize) -> isize {
     }
 }
 
+#[inline]
+pub fn cell_state(cell: &Cell) -> usize {
+    match *cell {
+        Cell::Empty => 0,
+        Cell::Occupied { .. } => 1,
+        Cell::Dying { state } | Cell::State { state } => state,
+    }
+}
+
 #[inline]
 fn bound_coordinate(left: isize, right: isize, coord: isize) -> isize {
     if coord < left || coord >= right {
@@ -81,12 +117,15 @@ fn bound_coordinate(left: isize, right: isize, coord: isize) -> isize {
 impl Board {
 
     pub fn new(cells: Box<BoardInternal>, width: Option<usize>, height: Option<usize>) -> Board {
+        Self::with_grid(cells, Grid::new(Topology::default(), width, height))
+    }
+
+    pub fn with_grid(cells: Box<BoardInternal>, grid: Grid) -> Board {
         Board {
             cells: cells,
             population: 0,
 
-            cols: width,
-            rows: height,
+            grid,
         }
     }
 
@@ -97,9 +136,14 @@ impl Board {
     }
 
     #[inline]
-    fn constrain_board(&self, col: isize, row: isize) -> (isize, isize) {
+    pub fn constrain_board(&self, col: isize, row: isize) -> Option<(isize, isize)> {
+
+        // ensure cell coordinates lie inside limits, returns None
+        // for cells beyond edges of a bounded plane
 
-        // ensure cell coordinates lie inside limits
+        if self.grid.topology != Topology::default() {
+            return self.grid.constrain(col, row);
+        }
 
         let mut new_col = col;
         let mut new_row = row;
@@ -107,7 +151,7 @@ impl Board {
         let mut left: usize;
         let mut right: usize;
 
-        if let Some(cols) = self.cols {
+        if let Some(cols) = self.grid.cols {
             if cols % 2 == 0 {
                 left = cols / 2;
                 right = left;
@@ -118,7 +162,7 @@ impl Board {
             new_col = bound_coordinate(-(left as isize), right as isize, col);
         }
 
-        if let Some(rows) = self.rows {
+        if let Some(rows) = self.grid.rows {
             if rows % 2 == 0 {
                 left = rows / 2;
                 right = left;
@@ -129,35 +173,45 @@ impl Board {
             new_row = bound_coordinate(-(left as isize), right as isize, row)
         }
 
-        (new_col, new_row)
+        Some((new_col, new_row))
     }
 
     fn ensure_cell(&mut self, col: isize, row: isize) {
-        let (col, row) = self.constrain_board(col, row);
-        self.cells.ensure_cell(col, row);
+        if let Some((col, row)) = self.constrain_board(col, row) {
+            self.cells.ensure_cell(col, row);
+        }
     }
 
-    pub fn born_at_gen(&mut self, col: isize, row: isize, gen: usize) {
-        if !self.is_alive(col, row) {
+    fn occupy(&mut self, col: isize, row: isize, cell: Cell) {
+
+        let (col, row) = match self.constrain_board(col, row) {
+            Some(coords) => coords,
+            // nothing can live beyond edges
+            None => return
+        };
 
-            self.ensure_cell(col, row);
+        self.ensure_cell(col, row);
 
-            // we must allocate 8 cells around current cell because
-            // new species can potentially born there, so we
-            // have to check them on next iteration
+        // we must allocate 8 cells around current cell because
+        // new species can potentially born there, so we
+        // have to check them on next iteration
 
-            self.ensure_cell(col - 1, row);
-            self.ensure_cell(col - 1, row - 1);
-            self.ensure_cell(col, row - 1);
-            self.ensure_cell(col + 1, row - 1);
-            self.ensure_cell(col + 1, row);
-            self.ensure_cell(col + 1, row + 1);
-            self.ensure_cell(col, row + 1);
-            self.ensure_cell(col - 1, row + 1);
+        self.ensure_cell(col - 1, row);
+        self.ensure_cell(col - 1, row - 1);
+        self.ensure_cell(col, row - 1);
+        self.ensure_cell(col + 1, row - 1);
+        self.ensure_cell(col + 1, row);
+        self.ensure_cell(col + 1, row + 1);
+        self.ensure_cell(col, row + 1);
+        self.ensure_cell(col - 1, row + 1);
 
-            let (col, row) = self.constrain_board(col, row);
-            self.population += 1;
-            self.cells.set_cell(col, row, Cell::Occupied { gen: gen });
+        self.population += 1;
+        self.cells.set_cell(col, row, cell);
+    }
+
+    pub fn born_at_gen(&mut self, col: isize, row: isize, gen: usize) {
+        if !self.is_alive(col, row) {
+            self.occupy(col, row, Cell::Occupied { gen });
         }
     }
 
@@ -165,35 +219,78 @@ impl Board {
         self.born_at_gen(col, row, 1);
     }
 
+    pub fn set_dying(&mut self, col: isize, row: isize, state: usize) {
+
+        // dying cells don't need their neighbours to be allocated,
+        // since they can't give birth to new cells
+
+        if self.is_alive(col, row) {
+            self.population -= 1;
+        }
+
+        if let Some((col, row)) = self.constrain_board(col, row) {
+            self.cells.ensure_cell(col, row);
+            self.cells.set_cell(col, row, Cell::Dying { state });
+        }
+    }
+
+    pub fn set_state(&mut self, col: isize, row: isize, state: usize) {
+
+        // cells of rule tables in states 2 and above may cause transitions
+        // of their neighbours, so the neighbours are allocated as well
+
+        if !self.is_alive(col, row) {
+            self.occupy(col, row, Cell::State { state });
+        }
+    }
+
+    pub fn set_cell(&mut self, col: isize, row: isize, cell: Cell) {
+
+        // replace a cell of any kind with another one
+
+        if self.get_cell(col, row) != Cell::Empty {
+            self.kill_at(col, row);
+        }
+
+        match cell {
+            Cell::Occupied { gen } => self.born_at_gen(col, row, gen),
+            Cell::Dying { state } => self.set_dying(col, row, state),
+            Cell::State { state } => self.set_state(col, row, state),
+            Cell::Empty => {}
+        }
+    }
+
     #[inline]
     pub fn kill_at(&mut self, col: isize, row: isize) {
-        let (col, row) = self.constrain_board(col, row);
-        self.population -= 1;
-        self.cells.rm_cell(col, row);
+        if self.is_alive(col, row) {
+            self.population -= 1;
+        }
+
+        if let Some((col, row)) = self.constrain_board(col, row) {
+            self.cells.rm_cell(col, row);
+        }
     }
 
     #[inline]
     pub fn is_alive(&self, col: isize, row: isize) -> bool {
-        self.get_cell(col, row) != Cell::Empty
+        matches!(self.get_cell(col, row), Cell::Occupied { .. } | Cell::State { .. })
     }
 
     pub fn is_infinite(&self) -> bool {
         // returns true if board is infinite at at least one direction
         // and false otherwise
-        self.cols.is_none() || self.rows.is_none()
+        self.grid.cols.is_none() || self.grid.rows.is_none()
     }
 
     pub fn get_cell(&self, col: isize, row: isize) -> Cell {
-        // if cell is not yet initialized it is considered as free
-        let (col, row) = self.constrain_board(col, row);
+        // if cell is not yet initialized or lies beyond edges it is considered as free
+        let (col, row) = match self.constrain_board(col, row) {
+            Some(coords) => coords,
+            None => return Cell::Empty
+        };
 
         match self.cells.get_cell(col, row) {
-            Some(x) => {
-                match x {
-                   &Cell::Occupied {gen} => *x,
-                   _ => Cell::Empty,
-                }
-            }
+            Some(x) => *x,
             None => Cell::Empty
         }
     }
@@ -201,10 +298,14 @@ impl Board {
     pub fn get_cell_gen(&self, col: isize, row: isize) -> usize {
         match self.get_cell(col, row) {
             Cell::Occupied { gen } => gen,
-            Cell::Empty => 0
+            _ => 0
         }
     }
 
+    pub fn get_cell_state(&self, col: isize, row: isize) -> usize {
+        cell_state(&self.get_cell(col, row))
+    }
+
     pub fn get_vicinity(&self, col: isize, row: isize) -> Vec<bool> {
         // get contents of 8 neighbours of a given cell
 
@@ -224,12 +325,17 @@ impl Board {
 
     #[inline]
     pub fn get_cols(&self) -> Option<usize> {
-        self.cols
+        self.grid.cols
     }
 
     #[inline]
     pub fn get_rows(&self) -> Option<usize> {
-        self.rows
+        self.grid.rows
+    }
+
+    #[inline]
+    pub fn get_grid(&self) -> Grid {
+        self.grid
     }
 
     #[inline]
@@ -237,6 +343,54 @@ impl Board {
         self.population
     }
 
+    #[inline]
+    pub fn get_allocated(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn next_generation(&self, rule: &Rule) -> Option<Board> {
+        // fast path for backends that support whole board stepping,
+        // wrapped boards are always processed cell by cell
+        if self.grid.is_bounded() {
+            return None;
+        }
+
+        self.cells.next_generation(rule).map(|(cells, population)| Board {
+            cells,
+            population,
+
+            grid: self.grid,
+        })
+    }
+
+    pub fn get_cells(&self) -> Vec<(Coord, Cell)> {
+        // coordinates and contents of all live and dying cells
+        self.iter().filter(|x| x.state != 0).
+            map(|x| (x.coord, self.get_cell(x.coord.col, x.coord.row))).collect()
+    }
+
+    pub fn get_bounding_box(&self) -> Option<(Coord, Coord)> {
+        // returns top left and bottom right corners of the smallest
+        // rectangle containing all live and dying cells or None if
+        // board is empty
+
+        let mut bbox: Option<(Coord, Coord)> = None;
+
+        for CellDesc { coord, state, .. } in self.iter() {
+            if state != 0 {
+                bbox = match bbox {
+                    Some((top_left, bottom_right)) => Some((
+                        Coord { col: top_left.col.min(coord.col), row: top_left.row.min(coord.row) },
+                        Coord { col: bottom_right.col.max(coord.col), row: bottom_right.row.max(coord.row) }
+                    )),
+                    None => Some((coord, coord))
+                };
+            }
+        }
+
+        bbox
+    }
+
     pub fn iter(&self) -> BoardIterator {
         BoardIterator {
             board: self,
@@ -263,13 +417,14 @@ impl<'a> Iterator for BoardIterator<'a> {
 
                 let gen = match cell {
                     Cell::Occupied { gen } => gen,
-                    Cell::Empty => 0
+                    _ => 0
                 };
 
                 Some(CellDesc {
                     coord: Coord { col: col, row: row },
                     gen: gen,
                     is_alive: self.board.is_alive(col, row),
+                    state: self.board.get_cell_state(col, row),
                     new_line: false
                 })
             }
@@ -282,7 +437,7 @@ impl<'a> Iterator for BoardIterator<'a> {
 
 #[test]
 fn test_board_ok() {
-    let mut my_board = Board::new(new_hashed(), Some(10), Some(10));
+    let mut my_board = Board::new(HashedBoard::new(), Some(10), Some(10));
 
     // set some existing cells
     my_board.born_at(0, 0);
@@ -310,7 +465,7 @@ fn test_board_ok() {
 
 #[test]
 fn test_board_iter() {
-    let mut my_board = Board::new(new_hashed(), Some(10), Some(10));
+    let mut my_board = Board::new(HashedBoard::new(), Some(10), Some(10));
 
     my_board.born_at(0, 0);
     my_board.born_at(1, 1);
@@ -320,7 +475,7 @@ fn test_board_iter() {
 
     let mut ctr = 0;
 
-    for CellDesc { coord, is_alive, .. } in my_board.into_iter() {
+    for CellDesc { is_alive, .. } in my_board.iter() {
         if is_alive {
             ctr += 1;
         }
@@ -331,7 +486,7 @@ fn test_board_iter() {
 
 #[test]
 fn test_glyder() {
-    let mut my_board = Board::new(new_hashed(), Some(10), Some(10));
+    let mut my_board = Board::new(HashedBoard::new(), Some(10), Some(10));
 
     my_board.born_at(0, 0);
     my_board.born_at(1, 1);
@@ -371,9 +526,22 @@ fn test_cycle() {
     assert_eq!(cycle(-6, -5, -4), -5);
 }
 
+#[test]
+fn test_bounding_box() {
+    let mut my_board = Board::new(HashedBoard::new(), None, None);
+
+    assert_eq!(my_board.get_bounding_box(), None);
+
+    my_board.born_at(-3, 2);
+    my_board.born_at(4, -1);
+    my_board.born_at(0, 5);
+
+    assert_eq!(my_board.get_bounding_box(), Some((Coord { col: -3, row: -1 }, Coord { col: 4, row: 5 })));
+}
+
 #[test]
 fn test_restricted_board() {
-    let mut my_board = Board::new(new_hashed(), Some(10), Some(10));
+    let mut my_board = Board::new(HashedBoard::new(), Some(10), Some(10));
 
     my_board.born_at(5, 2);
     assert_eq!(my_board.is_alive(-5, 2), true);
@@ -381,3 +549,41 @@ fn test_restricted_board() {
     my_board.born_at(0, -7);
     assert_eq!(my_board.is_alive(0, 3), true);
 }
+
+#[test]
+fn test_bounded_plane() {
+    let mut my_board = Board::with_grid(HashedBoard::new(), Grid::new(Topology::Plane, Some(10), Some(10)));
+
+    // cells beyond edges are ignored
+    my_board.born_at(5, 2);
+    assert!(!my_board.is_alive(-5, 2));
+    assert_eq!(my_board.get_population(), 0);
+
+    // and neighbours beyond edges are dead
+    my_board.born_at(4, 2);
+    assert_eq!(my_board.get_vicinity(4, 2).into_iter().filter(|&x| x).count(), 0);
+    assert_eq!(my_board.get_population(), 1);
+}
+
+#[test]
+fn test_dying_cells() {
+    let mut my_board = Board::new(HashedBoard::new(), None, None);
+
+    my_board.born_at(0, 0);
+    my_board.born_at(1, 0);
+    my_board.set_dying(0, 0, 2);
+
+    // dying cells are neither alive nor counted in population
+    assert_eq!(my_board.get_cell(0, 0), Cell::Dying { state: 2 });
+    assert_eq!(my_board.get_cell_state(0, 0), 2);
+    assert!(!my_board.is_alive(0, 0));
+    assert_eq!(my_board.get_population(), 1);
+
+    my_board.kill_at(0, 0);
+    assert_eq!(my_board.get_cell(0, 0), Cell::Empty);
+    assert_eq!(my_board.get_population(), 1);
+
+    my_board.set_cell(1, 0, Cell::Dying { state: 3 });
+    assert_eq!(my_board.get_population(), 0);
+    assert_eq!(my_board.iter().filter(|x| x.state == 3).count(), 1);
+}
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Iter;
+
+use ::board::{BoardInternal, Cell, CellIterType};
+use ::core::Rule;
+
+static ALIVE_CELL: Cell = Cell::Occupied { gen: 1 };
+static EMPTY_CELL: Cell = Cell::Empty;
+
+const WORD_BITS: isize = 64;
+
+/// Dense board, every word keeps 64 cells of a row, so cell (col, row)
+/// is the bit number col % 64 of the word (col / 64, row). Next generation
+/// is computed for all 64 cells of a word at once with bitwise adders.
+/// Live cells with their generations are kept for every non-empty word.
+pub struct BitPacked {
+    words: HashMap<(isize, isize), u64>,
+    cells: HashMap<(isize, isize), Box<[Cell; 64]>>,
+}
+
+pub struct CellsIterator<'a> {
+    iter: Iter<'a, (isize, isize), u64>,
+    cells: &'a HashMap<(isize, isize), Box<[Cell; 64]>>,
+    cur: Option<(isize, isize, u64, Option<&'a [Cell; 64]>)>,
+    bit: isize,
+}
+
+impl<'a> Iterator for CellsIterator<'a> {
+
+    type Item = CellIterType;
+
+    fn next(&mut self) -> Option<CellIterType> {
+
+        loop {
+
+            if let Some((word_col, row, word, cells)) = self.cur {
+                if self.bit < WORD_BITS {
+                    let cell = if (word >> self.bit) & 1 == 1 {
+                        cells.map_or(ALIVE_CELL, |cells| cells[self.bit as usize])
+                    } else {
+                        EMPTY_CELL
+                    };
+                    let col = word_col * WORD_BITS + self.bit;
+
+                    self.bit += 1;
+
+                    return Some((col, row, cell));
+                }
+            }
+
+            match self.iter.next() {
+                Some((&(word_col, row), &word)) => {
+                    let cells = self.cells.get(&(word_col, row)).map(|cells| &**cells);
+                    self.cur = Some((word_col, row, word, cells));
+                    self.bit = 0;
+                }
+                None => return None
+            }
+
+        }
+
+    }
+
+}
+
+impl<'a> IntoIterator for &'a BitPacked {
+    type Item = CellIterType;
+    type IntoIter = CellsIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CellsIterator { iter: self.words.iter(), cells: &self.cells, cur: None, bit: 0 }
+    }
+}
+
+#[inline]
+fn split_col(col: isize) -> (isize, isize) {
+    // word column and bit index of a cell, works for negative columns too
+    (col >> 6, col & (WORD_BITS - 1))
+}
+
+#[inline]
+fn empty_cells() -> Box<[Cell; 64]> {
+    Box::new([Cell::Empty; WORD_BITS as usize])
+}
+
+#[inline]
+fn add_to_counter(counter: &mut [u64; 4], bits: u64) {
+    // adds one bit to 64 independent 4-bit counters stored in bit planes
+    let mut carry = bits;
+    for plane in counter.iter_mut() {
+        let next_carry = *plane & carry;
+        *plane ^= carry;
+        carry = next_carry;
+    }
+}
+
+#[inline]
+fn count_mask(counter: &[u64; 4], cnt: usize) -> u64 {
+    // bits of the cells which counters are equal to the given value
+    let mut mask = !0u64;
+    for (idx, plane) in counter.iter().enumerate() {
+        mask &= if (cnt >> idx) & 1 == 1 { *plane } else { !*plane };
+    }
+    mask
+}
+
+impl BoardInternal for BitPacked {
+
+    fn get_cell(&self, col: isize, row: isize) -> Option<&Cell> {
+        let (word_col, bit) = split_col(col);
+        match self.words.get(&(word_col, row)) {
+            Some(word) if (word >> bit) & 1 == 1 => self.cells.get(&(word_col, row)).map(|cells| &cells[bit as usize]),
+            Some(_) => Some(&EMPTY_CELL),
+            None => None
+        }
+    }
+
+    fn set_cell(&mut self, col: isize, row: isize, val: Cell) {
+        let (word_col, bit) = split_col(col);
+        let word = self.words.entry((word_col, row)).or_insert(0);
+        match val {
+            Cell::Occupied { .. } => {
+                *word |= 1 << bit;
+                self.cells.entry((word_col, row)).or_insert_with(empty_cells)[bit as usize] = val;
+            },
+            // packed board is used only for two state rules
+            _ => {
+                *word &= !(1 << bit);
+                if *word == 0 {
+                    self.cells.remove(&(word_col, row));
+                } else if let Some(cells) = self.cells.get_mut(&(word_col, row)) {
+                    cells[bit as usize] = Cell::Empty;
+                }
+            },
+        }
+    }
+
+    fn ensure_cell(&mut self, col: isize, row: isize) {
+        let (word_col, _) = split_col(col);
+        self.words.entry((word_col, row)).or_insert(0);
+    }
+
+    fn rm_cell(&mut self, col: isize, row: isize) {
+        self.set_cell(col, row, Cell::Empty);
+    }
+
+    fn len(&self) -> usize {
+        self.words.len() * WORD_BITS as usize
+    }
+
+    fn get_iter<'a>(&'a self) -> Box<Iterator<Item=CellIterType> + 'a> {
+        Box::new(IntoIterator::into_iter(self))
+    }
+
+    fn next_generation(&self, rule: &Rule) -> Option<(Box<BoardInternal>, u64)> {
+
+        // cells far from live ones can't be born if a dead cell with no
+        // live neighbours stays dead, otherwise the whole plane is needed,
+        // states of dying cells of Generations rules can't be packed and
+        // only numbers of neighbours are computed
+        if rule.is_born(0) || rule.get_states() > 2 || !rule.is_life_like() || !rule.is_totalistic() {
+            return None;
+        }
+
+        let mut candidates: HashSet<(isize, isize)> = HashSet::new();
+
+        for (&(word_col, row), &word) in self.words.iter() {
+            if word != 0 {
+                for d_row in -1..2 {
+                    for d_col in -1..2 {
+                        candidates.insert((word_col + d_col, row + d_row));
+                    }
+                }
+            }
+        }
+
+        let mut next_words: HashMap<(isize, isize), u64> = HashMap::new();
+        let mut next_cells: HashMap<(isize, isize), Box<[Cell; 64]>> = HashMap::new();
+        let mut population = 0;
+
+        for (word_col, row) in candidates {
+            let word = self.next_word(word_col, row, rule);
+            if word != 0 {
+                population += word.count_ones() as u64;
+                next_words.insert((word_col, row), word);
+                next_cells.insert((word_col, row), self.next_cells(word_col, row, word));
+            }
+        }
+
+        // allocate empty words around live ones, so all neighbours of
+        // live cells are present on the board as other backends do
+        let live_words: Vec<(isize, isize)> = next_words.keys().cloned().collect();
+
+        for (word_col, row) in live_words {
+            for d_row in -1..2 {
+                for d_col in -1..2 {
+                    next_words.entry((word_col + d_col, row + d_row)).or_insert(0);
+                }
+            }
+        }
+
+        Some((Box::new(BitPacked { words: next_words, cells: next_cells }), population))
+    }
+
+}
+
+impl BitPacked {
+
+    pub fn new() -> Box<BoardInternal> {
+        Box::new(BitPacked { words: HashMap::new(), cells: HashMap::new() })
+    }
+
+    #[inline]
+    fn get_word(&self, word_col: isize, row: isize) -> u64 {
+        *self.words.get(&(word_col, row)).unwrap_or(&0)
+    }
+
+    fn next_word(&self, word_col: isize, row: isize, rule: &Rule) -> u64 {
+
+        // count live neighbours of all 64 cells of the word at once
+
+        let mut counter = [0u64; 4];
+
+        for d_row in -1..2 {
+
+            let word = self.get_word(word_col, row + d_row);
+            let left = self.get_word(word_col - 1, row + d_row);
+            let right = self.get_word(word_col + 1, row + d_row);
+
+            // bit i of these words holds the left and the right neighbour of the cell i
+            add_to_counter(&mut counter, (word << 1) | (left >> 63));
+            add_to_counter(&mut counter, (word >> 1) | (right << 63));
+
+            if d_row != 0 {
+                add_to_counter(&mut counter, word);
+            }
+        }
+
+        let mut birth = 0;
+        let mut survival = 0;
+
+        for cnt in 0..9 {
+            if rule.is_born(cnt) {
+                birth |= count_mask(&counter, cnt);
+            }
+            if rule.survives(cnt) {
+                survival |= count_mask(&counter, cnt);
+            }
+        }
+
+        let center = self.get_word(word_col, row);
+
+        (!center & birth) | (center & survival)
+    }
+
+    fn next_cells(&self, word_col: isize, row: isize, next_word: u64) -> Box<[Cell; 64]> {
+
+        // live cells of the next generation, survivors get one generation older
+        // and newborn cells start from the first one
+
+        let prev = self.cells.get(&(word_col, row));
+        let mut cells = empty_cells();
+        let mut bits = next_word;
+
+        while bits != 0 {
+            let bit = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+
+            let gen = match prev.map(|prev| prev[bit]) {
+                Some(Cell::Occupied { gen }) => gen + 1,
+                _ => 1,
+            };
+
+            cells[bit] = Cell::Occupied { gen };
+        }
+
+        cells
+    }
+
+}
+
+
+#[test]
+fn test_packed_cells() {
+    let mut board = BitPacked { words: HashMap::new(), cells: HashMap::new() };
+
+    board.set_cell(-1, 5, Cell::Occupied { gen: 3 });
+    board.set_cell(64, 5, Cell::Occupied { gen: 1 });
+
+    assert_eq!(board.get_cell(-1, 5), Some(&Cell::Occupied { gen: 3 }));
+    assert_eq!(board.get_cell(-2, 5), Some(&EMPTY_CELL));
+    assert_eq!(board.get_cell(64, 5), Some(&ALIVE_CELL));
+    assert_eq!(board.get_cell(0, 5), None);
+
+    board.rm_cell(-1, 5);
+    assert_eq!(board.get_cell(-1, 5), Some(&EMPTY_CELL));
+}
+
+#[test]
+fn test_packed_blinker_across_words() {
+    // vertical blinker standing on the boundary between two words
+    let mut board = BitPacked { words: HashMap::new(), cells: HashMap::new() };
+
+    board.set_cell(-1, 0, ALIVE_CELL);
+    board.set_cell(0, 0, ALIVE_CELL);
+    board.set_cell(1, 0, ALIVE_CELL);
+
+    let (next, population) = board.next_generation(&Rule::default()).unwrap();
+
+    assert_eq!(population, 3);
+    assert_eq!(next.get_cell(0, -1), Some(&ALIVE_CELL));
+    // the middle cell survives and gets older
+    assert_eq!(next.get_cell(0, 0), Some(&Cell::Occupied { gen: 2 }));
+    assert_eq!(next.get_cell(0, 1), Some(&ALIVE_CELL));
+    assert_eq!(next.get_cell(-1, 0), Some(&EMPTY_CELL));
+}
@@ -0,0 +1,355 @@
+//! Topologies of bounded boards in Golly notation.
+//!
+//! A bounded grid is written as a letter followed by width and height,
+//! e.g. "T30,20" is a torus of 30x20 cells, zero size means the board is
+//! infinite in that direction. Supported topologies are
+//!
+//! * `P` - plane, cells beyond edges are always dead
+//! * `T` - torus, a shift may be added to one of the sizes, "T30+2,20"
+//!   joins top and bottom edges with a shift of 2 cells to the right,
+//!   "T30,20+2" joins left and right edges with a shift of 2 cells down
+//! * `K` - Klein bottle, the twisted pair of edges is marked with an
+//!   asterisk, "K30*,20" flips cells crossing top and bottom edges
+//! * `C` - cross-surface, both pairs of edges are twisted
+//! * `S` - sphere, top edge is joined to the left one and bottom edge to
+//!   the right one, so the board must be square
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Formatter, Display};
+use std::str::FromStr;
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Topology {
+    Plane,
+    // col_shift is applied crossing top and bottom edges,
+    // row_shift crossing left and right ones
+    Torus { col_shift: isize, row_shift: isize },
+    // twisted_rows means top and bottom edges are joined flipped,
+    // otherwise left and right ones are
+    KleinBottle { twisted_rows: bool },
+    CrossSurface,
+    Sphere,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology::Torus { col_shift: 0, row_shift: 0 }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TopologyError(String);
+
+impl Display for TopologyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Wrong topology {}", self.0)
+    }
+}
+
+impl Error for TopologyError {
+    fn description(&self) -> &str {
+        "Wrong topology"
+    }
+}
+
+/// Topology together with board sizes, `None` size means the board
+/// is infinite in that direction.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Grid {
+    pub topology: Topology,
+    pub cols: Option<usize>,
+    pub rows: Option<usize>,
+}
+
+fn parse_size(size_str: &str) -> Option<(Option<usize>, isize, bool)> {
+
+    // parses one size of the grid, e.g. "30", "30+2", "30-1" or "30*",
+    // returns the size, the shift and the twist mark
+
+    let (size_str, twisted) = match size_str.strip_suffix('*') {
+        Some(stripped) => (stripped, true),
+        None => (size_str, false)
+    };
+
+    let (size_str, shift) = match size_str.find(['+', '-']) {
+        Some(idx) => (&size_str[..idx], match size_str[idx..].trim_start_matches('+').parse::<isize>() {
+            Ok(shift) => shift,
+            Err(_) => return None,
+        }),
+        None => (size_str, 0)
+    };
+
+    let size = match size_str.parse::<usize>() {
+        Ok(0) => None,
+        Ok(size) => Some(size),
+        Err(_) => return None,
+    };
+
+    Some((size, shift, twisted))
+}
+
+impl Grid {
+
+    pub fn new(topology: Topology, cols: Option<usize>, rows: Option<usize>) -> Self {
+        Grid { topology, cols, rows }
+    }
+
+    pub fn parse(grid_str: &str) -> Result<Self, TopologyError> {
+
+        let err = || TopologyError(String::from(grid_str));
+
+        let grid_str = grid_str.trim();
+
+        let letter = match grid_str.chars().next() {
+            Some(letter) => letter.to_ascii_uppercase(),
+            None => return Err(err()),
+        };
+
+        let sizes: Vec<&str> = grid_str[1..].split(',').collect();
+
+        // sphere has only one size, others may omit the height for a square board
+        let (width, height) = match sizes.len() {
+            1 => (sizes[0], sizes[0]),
+            2 => (sizes[0], sizes[1]),
+            _ => return Err(err()),
+        };
+
+        let (cols, col_shift, cols_twisted) = parse_size(width).ok_or_else(&err)?;
+        let (rows, row_shift, rows_twisted) = parse_size(height).ok_or_else(&err)?;
+
+        let bounded = cols.is_some() && rows.is_some();
+
+        let topology = match letter {
+            'P' if !cols_twisted && !rows_twisted && col_shift == 0 && row_shift == 0 => Topology::Plane,
+            // only one pair of edges may be shifted and only if both are bounded
+            'T' if !cols_twisted && !rows_twisted && (col_shift == 0 || row_shift == 0) &&
+                   (bounded || col_shift == 0 && row_shift == 0) =>
+                Topology::Torus { col_shift, row_shift },
+            'K' if bounded && cols_twisted != rows_twisted && col_shift == 0 && row_shift == 0 =>
+                Topology::KleinBottle { twisted_rows: cols_twisted },
+            'C' if bounded && !cols_twisted && !rows_twisted && col_shift == 0 && row_shift == 0 =>
+                Topology::CrossSurface,
+            'S' if bounded && cols == rows && !cols_twisted && !rows_twisted &&
+                   col_shift == 0 && row_shift == 0 => Topology::Sphere,
+            _ => return Err(err()),
+        };
+
+        Ok(Grid::new(topology, cols, rows))
+    }
+
+    #[inline]
+    pub fn is_bounded(&self) -> bool {
+        // true if board is finite at least in one direction
+        self.cols.is_some() || self.rows.is_some()
+    }
+
+    pub fn col_range(&self) -> Option<(isize, isize)> {
+        // first and last columns of the board, None if it's infinite
+        self.cols.map(range)
+    }
+
+    pub fn row_range(&self) -> Option<(isize, isize)> {
+        self.rows.map(range)
+    }
+
+    pub fn constrain(&self, col: isize, row: isize) -> Option<(isize, isize)> {
+
+        // maps coordinates of a cell to the coordinates of the cell on the board
+        // it is glued to, None means the cell lies beyond edges and is always dead
+
+        let (left, width) = bounds(self.cols);
+        let (top, height) = bounds(self.rows);
+
+        // coordinates relative to the top left corner
+        let mut x = col + left;
+        let mut y = row + top;
+
+        match self.topology {
+
+            Topology::Plane => {
+                if self.cols.is_some() && (x < 0 || x >= width) ||
+                   self.rows.is_some() && (y < 0 || y >= height) {
+                    return None;
+                }
+            },
+
+            Topology::Torus { col_shift, row_shift } => {
+                if self.rows.is_some() {
+                    x += y.div_euclid(height) * col_shift;
+                    y = y.rem_euclid(height);
+                }
+                if self.cols.is_some() {
+                    y += x.div_euclid(width) * row_shift;
+                    x = x.rem_euclid(width);
+                    if self.rows.is_some() {
+                        y = y.rem_euclid(height);
+                    }
+                }
+            },
+
+            Topology::KleinBottle { twisted_rows: true } => {
+                if y.div_euclid(height) % 2 != 0 {
+                    x = width - 1 - x;
+                }
+                y = y.rem_euclid(height);
+                x = x.rem_euclid(width);
+            },
+
+            Topology::KleinBottle { twisted_rows: false } => {
+                if x.div_euclid(width) % 2 != 0 {
+                    y = height - 1 - y;
+                }
+                x = x.rem_euclid(width);
+                y = y.rem_euclid(height);
+            },
+
+            Topology::CrossSurface => {
+                if y.div_euclid(height) % 2 != 0 {
+                    x = width - 1 - x;
+                }
+                y = y.rem_euclid(height);
+                if x.div_euclid(width) % 2 != 0 {
+                    y = height - 1 - y;
+                }
+                x = x.rem_euclid(width);
+            },
+
+            Topology::Sphere => {
+                // crossing an edge moves the cell to the adjacent edge,
+                // cells beyond corners have no counterpart
+                let size = width;
+                let x_inside = x >= 0 && x < size;
+                let y_inside = y >= 0 && y < size;
+
+                if x_inside && !y_inside {
+                    let (new_x, new_y) = if y < 0 { (-y - 1, x) } else { (2 * size - 1 - y, x) };
+                    x = new_x;
+                    y = new_y;
+                } else if y_inside && !x_inside {
+                    let (new_x, new_y) = if x < 0 { (y, -x - 1) } else { (y, 2 * size - 1 - x) };
+                    x = new_x;
+                    y = new_y;
+                }
+
+                if x < 0 || x >= size || y < 0 || y >= size {
+                    return None;
+                }
+            },
+        }
+
+        Some((x - left, y - top))
+    }
+
+}
+
+#[inline]
+fn bounds(size: Option<usize>) -> (isize, isize) {
+    // board of the given size spans cells from -left to size - left - 1,
+    // returns left and size, infinite board is not shifted
+    match size {
+        Some(size) => ((size / 2) as isize, size as isize),
+        None => (0, 0),
+    }
+}
+
+#[inline]
+fn range(size: usize) -> (isize, isize) {
+    let (left, size) = bounds(Some(size));
+    (-left, size - left - 1)
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid::new(Topology::default(), None, None)
+    }
+}
+
+impl FromStr for Grid {
+    type Err = TopologyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::parse(s)
+    }
+}
+
+impl Display for Grid {
+
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+
+        let cols = self.cols.unwrap_or(0);
+        let rows = self.rows.unwrap_or(0);
+
+        let shift = |shift: isize| if shift > 0 {
+            format!("+{}", shift)
+        } else if shift < 0 {
+            format!("{}", shift)
+        } else {
+            String::new()
+        };
+
+        match self.topology {
+            Topology::Plane => write!(f, "P{},{}", cols, rows),
+            Topology::Torus { col_shift, row_shift } =>
+                write!(f, "T{}{},{}{}", cols, shift(col_shift), rows, shift(row_shift)),
+            Topology::KleinBottle { twisted_rows: true } => write!(f, "K{}*,{}", cols, rows),
+            Topology::KleinBottle { twisted_rows: false } => write!(f, "K{},{}*", cols, rows),
+            Topology::CrossSurface => write!(f, "C{},{}", cols, rows),
+            Topology::Sphere => write!(f, "S{}", cols),
+        }
+
+    }
+
+}
+
+
+#[test]
+fn test_parse_grid() {
+    assert_eq!(Grid::parse("T30,20"), Ok(Grid::new(Topology::default(), Some(30), Some(20))));
+    assert_eq!(Grid::parse("T0,20"), Ok(Grid::new(Topology::default(), None, Some(20))));
+    assert_eq!(Grid::parse("P10"), Ok(Grid::new(Topology::Plane, Some(10), Some(10))));
+    assert_eq!(Grid::parse("T30+2,20").unwrap().topology, Topology::Torus { col_shift: 2, row_shift: 0 });
+    assert_eq!(Grid::parse("T30,20-1").unwrap().topology, Topology::Torus { col_shift: 0, row_shift: -1 });
+    assert_eq!(Grid::parse("K30,20*").unwrap().topology, Topology::KleinBottle { twisted_rows: false });
+    assert_eq!(Grid::parse("S10"), Ok(Grid::new(Topology::Sphere, Some(10), Some(10))));
+
+    assert!(Grid::parse("").is_err());
+    assert!(Grid::parse("X10,10").is_err());
+    assert!(Grid::parse("T10+1,10+1").is_err());
+    assert!(Grid::parse("K10,10").is_err());
+    assert!(Grid::parse("S10,20").is_err());
+    assert!(Grid::parse("C0,10").is_err());
+
+    for spec in ["P30,20", "T30+2,20", "T30,20-3", "K30*,20", "K30,20*", "C30,20", "S10"].iter() {
+        assert_eq!(format!("{}", Grid::parse(spec).unwrap()), *spec);
+    }
+}
+
+#[test]
+fn test_constrain() {
+    // 10x10 board spans cells from -5 to 4
+    let plane = Grid::new(Topology::Plane, Some(10), Some(10));
+    assert_eq!(plane.constrain(4, -5), Some((4, -5)));
+    assert_eq!(plane.constrain(5, 0), None);
+
+    let torus = Grid::new(Topology::default(), Some(10), Some(10));
+    assert_eq!(torus.constrain(5, -6), Some((-5, 4)));
+
+    let twisted = Grid::new(Topology::Torus { col_shift: 2, row_shift: 0 }, Some(10), Some(10));
+    assert_eq!(twisted.constrain(0, 5), Some((2, -5)));
+    assert_eq!(twisted.constrain(0, -6), Some((-2, 4)));
+
+    let klein = Grid::new(Topology::KleinBottle { twisted_rows: true }, Some(10), Some(10));
+    assert_eq!(klein.constrain(-5, 5), Some((4, -5)));
+    assert_eq!(klein.constrain(5, 0), Some((-5, 0)));
+
+    let cross = Grid::new(Topology::CrossSurface, Some(10), Some(10));
+    assert_eq!(cross.constrain(5, 0), Some((-5, -1)));
+    assert_eq!(cross.constrain(-5, -6), Some((4, 4)));
+
+    let sphere = Grid::new(Topology::Sphere, Some(10), Some(10));
+    assert_eq!(sphere.constrain(2, -6), Some((-5, 2)));
+    assert_eq!(sphere.constrain(5, 1), Some((1, 4)));
+    assert_eq!(sphere.constrain(5, 5), None);
+}
@@ -0,0 +1,165 @@
+//! Rotations, reflections and translations of patterns given by lists
+//! of cell coordinates.
+//!
+//! Rows go down as the board is drawn, so rotations are clockwise on
+//! the screen. Patterns keep the top left corner of their bounding box
+//! or of a given region when they are rotated or flipped, so they stay
+//! in place.
+
+use super::Coord;
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // left and right sides are swapped
+    FlipHorizontal,
+    // top and bottom sides are swapped
+    FlipVertical,
+    // rows become columns, the top left to bottom right diagonal stays in place
+    FlipDiagonal,
+    // the top right to bottom left diagonal stays in place
+    FlipAntiDiagonal,
+}
+
+impl Transform {
+
+    pub fn apply(&self, coord: Coord) -> Coord {
+
+        // transform a single cell around the origin
+
+        let Coord { col, row } = coord;
+
+        let (col, row) = match *self {
+            Transform::Rotate90 => (-row, col),
+            Transform::Rotate180 => (-col, -row),
+            Transform::Rotate270 => (row, -col),
+            Transform::FlipHorizontal => (-col, row),
+            Transform::FlipVertical => (col, -row),
+            Transform::FlipDiagonal => (row, col),
+            Transform::FlipAntiDiagonal => (-row, -col),
+        };
+
+        Coord { col, row }
+    }
+
+    pub fn swaps_sides(&self) -> bool {
+        // width and height of a pattern are swapped by the transform
+        matches!(*self, Transform::Rotate90 | Transform::Rotate270 |
+                        Transform::FlipDiagonal | Transform::FlipAntiDiagonal)
+    }
+
+}
+
+/// How cells of a pattern are combined with cells of a board it's
+/// placed on, `And` and `Overwrite` change the whole rectangle of
+/// the pattern including its dead cells
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StampMode {
+    // live cells of the pattern are added to the board
+    Or,
+    // live cells of the pattern toggle cells of the board
+    Xor,
+    // only cells alive both on the board and in the pattern are kept
+    And,
+    // the rectangle of the pattern replaces cells of the board
+    Overwrite,
+}
+
+pub fn bounding_box(coords: &[Coord]) -> Option<(Coord, Coord)> {
+
+    let first = match coords.first() {
+        Some(&coord) => coord,
+        None => return None,
+    };
+
+    Some(coords.iter().fold((first, first), |(tl, br), c| {
+        (Coord { col: tl.col.min(c.col), row: tl.row.min(c.row) },
+         Coord { col: br.col.max(c.col), row: br.row.max(c.row) })
+    }))
+
+}
+
+pub fn transform_region(coords: &[Coord], transform: Transform, top_left: Coord, bottom_right: Coord) -> Vec<Coord> {
+
+    // transform cells of a rectangular region, the transformed region
+    // has the same top left corner, but its sides may be swapped
+
+    let corner1 = transform.apply(top_left);
+    let corner2 = transform.apply(bottom_right);
+
+    let dx = top_left.col - corner1.col.min(corner2.col);
+    let dy = top_left.row - corner1.row.min(corner2.row);
+
+    coords.iter().map(|&coord| {
+        let Coord { col, row } = transform.apply(coord);
+        Coord { col: col + dx, row: row + dy }
+    }).collect()
+
+}
+
+pub fn transform(coords: &[Coord], transform: Transform) -> Vec<Coord> {
+
+    // transform cells keeping the top left corner of their bounding box
+
+    match bounding_box(coords) {
+        Some((top_left, bottom_right)) => transform_region(coords, transform, top_left, bottom_right),
+        None => Vec::new(),
+    }
+
+}
+
+pub fn translate(coords: &[Coord], dx: isize, dy: isize) -> Vec<Coord> {
+    coords.iter().map(|c| Coord { col: c.col + dx, row: c.row + dy }).collect()
+}
+
+
+#[test]
+fn test_transforms() {
+    use core::sorted_coords;
+
+    // glider moving to the bottom right
+    let glider = [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                  Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }];
+
+    // rotated clockwise it moves to the bottom left
+    assert_eq!(sorted_coords(transform(&glider, Transform::Rotate90)),
+               [Coord { col: 0, row: 0 }, Coord { col: 0, row: 1 }, Coord { col: 2, row: 1 },
+                Coord { col: 0, row: 2 }, Coord { col: 1, row: 2 }]);
+
+    // flipped horizontally it moves to the bottom left as well, but has another phase
+    assert_eq!(sorted_coords(transform(&glider, Transform::FlipHorizontal)),
+               [Coord { col: 1, row: 0 }, Coord { col: 0, row: 1 }, Coord { col: 0, row: 2 },
+                Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }]);
+
+    // four rotations or two flips give the same pattern
+    let mut coords = glider.to_vec();
+    for _ in 0..4 {
+        coords = transform(&coords, Transform::Rotate90);
+    }
+    assert_eq!(sorted_coords(coords), glider);
+
+    for &t in [Transform::Rotate180, Transform::FlipHorizontal, Transform::FlipVertical,
+               Transform::FlipDiagonal, Transform::FlipAntiDiagonal].iter() {
+        assert_eq!(sorted_coords(transform(&transform(&glider, t), t)), glider);
+    }
+
+    assert_eq!(sorted_coords(transform(&transform(&glider, Transform::FlipHorizontal), Transform::FlipVertical)),
+               sorted_coords(transform(&glider, Transform::Rotate180)));
+    assert_eq!(sorted_coords(transform(&transform(&glider, Transform::Rotate90), Transform::Rotate180)),
+               sorted_coords(transform(&glider, Transform::Rotate270)));
+
+    // bounding box stays in place
+    let moved = translate(&glider, -10, 5);
+    assert_eq!(bounding_box(&transform(&moved, Transform::FlipAntiDiagonal)),
+               Some((Coord { col: -10, row: 5 }, Coord { col: -8, row: 7 })));
+
+    // sides of a region are swapped keeping its top left corner
+    let line = [Coord { col: 5, row: 1 }, Coord { col: 6, row: 1 }];
+    assert_eq!(transform_region(&line, Transform::Rotate90, Coord { col: 5, row: 1 }, Coord { col: 8, row: 2 }),
+               [Coord { col: 6, row: 1 }, Coord { col: 6, row: 2 }]);
+
+    assert!(transform(&[], Transform::Rotate90).is_empty());
+}
@@ -109,6 +109,10 @@ impl BoardInternal for SymVecBased {
         self.set_cell(col, row, Cell::Empty);
     }
 
+    fn len(&self) -> usize {
+        self.cells.into_iter().map(|row| row.len()).sum()
+    }
+
     fn get_iter<'a>(&'a self) -> Box<Iterator<Item=CellIterType> + 'a> {
         Box::new(IntoIterator::into_iter(self))
     }
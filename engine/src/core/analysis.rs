@@ -0,0 +1,229 @@
+//! Detection of still lifes, oscillators and spaceships.
+//!
+//! Every generation the set of live cells is moved to have its top left
+//! corner at (0, 0) and remembered. When the same cells are met again the
+//! pattern has become periodic, its period is the distance between generations
+//! and the difference of top left corners gives the displacement. Cells are
+//! hashed to find candidates quickly.
+//!
+//! Moving patterns behave the same only on infinite boards and tori, so on
+//! other bounded boards only exact repetitions are detected.
+
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fmt::{Formatter, Display};
+use std::hash::{Hash, Hasher};
+
+use board::{Board, Cell, Coord, Topology};
+
+// how many generations are remembered, longer periods are not detected
+const MAX_HISTORY: usize = 4096;
+
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Periodicity {
+    Extinct,
+    Still,
+    Oscillator { period: usize },
+    Spaceship { period: usize, dx: isize, dy: isize },
+}
+
+impl Display for Periodicity {
+
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+
+        match *self {
+            Periodicity::Extinct => write!(f, "extinct"),
+            Periodicity::Still => write!(f, "still life"),
+            Periodicity::Oscillator { period } => write!(f, "oscillator p{}", period),
+            Periodicity::Spaceship { period, dx, dy } => write!(f, "spaceship p{} ({}, {})",
+                                                                period, dx, dy),
+        }
+
+    }
+
+}
+
+pub struct PeriodDetector {
+    // hash of normalized cells -> generations observed with that hash
+    seen: HashMap<u64, Vec<Observation>>,
+    // hashes in order of appearance to forget the oldest generations
+    history: VecDeque<u64>,
+
+    result: Option<Periodicity>,
+}
+
+struct Observation {
+    generation: usize,
+    top_left: Coord,
+    // sorted cells relative to the top left corner, equal hashes
+    // don't prove that generations are the same
+    cells: Vec<(Coord, usize)>,
+}
+
+fn normalize(board: &Board) -> Option<(Vec<(Coord, usize)>, Coord)> {
+
+    // returns sorted live and dying cells moved to have their top left
+    // corner at the origin and the corner itself, or None if the board is empty
+
+    let mut cells: Vec<(Coord, usize)> = board.get_cells().into_iter().map(|(coord, cell)| match cell {
+        Cell::Dying { state } | Cell::State { state } => (coord, state),
+        _ => (coord, 1)
+    }).collect();
+
+    if cells.is_empty() {
+        return None;
+    }
+
+    let grid = board.get_grid();
+
+    let movable = match grid.topology {
+        Topology::Torus { .. } => true,
+        _ => !grid.is_bounded()
+    };
+
+    let top_left = if movable {
+        Coord {
+            col: cells.iter().map(|&(c, _)| c.col).min().unwrap(),
+            row: cells.iter().map(|&(c, _)| c.row).min().unwrap(),
+        }
+    } else {
+        Coord { col: 0, row: 0 }
+    };
+
+    for cell in cells.iter_mut() {
+        cell.0 = Coord { col: cell.0.col - top_left.col, row: cell.0.row - top_left.row };
+    }
+
+    cells.sort_by_key(|&(c, state)| (c.row, c.col, state));
+
+    Some((cells, top_left))
+
+}
+
+fn hash_cells(cells: &[(Coord, usize)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for &(c, state) in cells {
+        (c.col, c.row, state).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl PeriodDetector {
+
+    pub fn new() -> Self {
+        PeriodDetector {
+            seen: HashMap::new(),
+            history: VecDeque::new(),
+            result: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.seen.clear();
+        self.history.clear();
+        self.result = None;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        // nothing was observed since the last reset
+        self.history.is_empty() && self.result.is_none()
+    }
+
+    #[inline]
+    pub fn get_result(&self) -> Option<Periodicity> {
+        self.result
+    }
+
+    pub fn observe(&mut self, board: &Board, generation: usize) -> Option<Periodicity> {
+
+        // once pattern became periodic it stays so forever
+        if self.result.is_some() {
+            return self.result;
+        }
+
+        let (cells, top_left) = match normalize(board) {
+            Some(x) => x,
+            None => {
+                self.result = Some(Periodicity::Extinct);
+                return self.result;
+            }
+        };
+
+        let hash = hash_cells(&cells);
+
+        let repeated = self.seen.get(&hash).and_then(|observations| {
+            observations.iter().find(|prev| prev.cells == cells).map(|prev| (prev.generation, prev.top_left))
+        });
+
+        if let Some((prev_generation, prev_top_left)) = repeated {
+
+            let period = generation - prev_generation;
+            let dx = top_left.col - prev_top_left.col;
+            let dy = top_left.row - prev_top_left.row;
+
+            self.result = Some(if dx != 0 || dy != 0 {
+                Periodicity::Spaceship { period, dx, dy }
+            } else if period == 1 {
+                Periodicity::Still
+            } else {
+                Periodicity::Oscillator { period }
+            });
+
+            return self.result;
+        }
+
+        self.seen.entry(hash).or_default().push(Observation {
+            generation,
+            top_left,
+            cells,
+        });
+        self.history.push_back(hash);
+
+        if self.history.len() > MAX_HISTORY {
+            // generations only grow, so the oldest one comes first among equal hashes
+            if let Some(oldest) = self.history.pop_front() {
+                let forget = match self.seen.get_mut(&oldest) {
+                    Some(observations) => {
+                        observations.remove(0);
+                        observations.is_empty()
+                    },
+                    None => false
+                };
+
+                if forget {
+                    self.seen.remove(&oldest);
+                }
+            }
+        }
+
+        None
+
+    }
+
+}
+
+
+#[test]
+fn test_equal_hashes() {
+    use board::HashedBoard;
+
+    let mut board = Board::new(HashedBoard::new(), None, None);
+    board.born_at(0, 0);
+    board.born_at(1, 0);
+
+    let (cells, top_left) = normalize(&board).unwrap();
+
+    // another generation with the same hash but different cells
+    let mut detector = PeriodDetector::new();
+    detector.seen.insert(hash_cells(&cells), vec![Observation {
+        generation: 0,
+        top_left,
+        cells: vec![(Coord { col: 0, row: 0 }, 1)],
+    }]);
+
+    assert_eq!(detector.observe(&board, 1), None);
+    assert_eq!(detector.observe(&board, 2), Some(Periodicity::Still));
+}
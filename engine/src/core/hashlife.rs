@@ -0,0 +1,499 @@
+//! HashLife implementation, see Bill Gosper's "Exploiting regularities in
+//! large cellular spaces" for the original description.
+//!
+//! The board is stored as a quadtree where identical subtrees are shared,
+//! results of advancing every node are memoized, so highly regular patterns
+//! can be advanced by 2^k generations in one step. Node of level `k` covers
+//! a square of 2^k x 2^k cells, root node is always centered at (0, 0).
+//! ```ignore
+//! let mut universe = HashLife::new(Rule::default());
+//! let root = universe.build_root(&glider).unwrap();
+//! let root = universe.advance(root, 1 << 20);
+//! let coords = universe.to_coords(root);
+//! ```
+
+use std::collections::HashMap;
+
+use board::Coord;
+use super::Rule;
+
+type NodeId = usize;
+
+// leaf nodes are preallocated and always have these ids
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// memory is freed by rebuilding the universe when nodes count exceeds this limit
+const MAX_NODES: usize = 1 << 22;
+
+// half of the side of larger roots wouldn't fit into isize
+const MAX_ROOT_LEVEL: usize = 62;
+
+
+struct Node {
+    level: usize,
+
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+
+    population: u64,
+}
+
+pub struct HashLife {
+    rule: Rule,
+
+    nodes: Vec<Node>,
+
+    // maps children to the node that contains them to share identical subtrees
+    index: HashMap<(NodeId, NodeId, NodeId, NodeId), NodeId>,
+    // memoized results of advancing a node by 2^k generations
+    results: HashMap<(NodeId, usize), NodeId>,
+    // empty node of every level
+    empty: Vec<NodeId>,
+}
+
+impl HashLife {
+
+    pub fn new(rule: Rule) -> Self {
+
+        let mut universe = HashLife {
+            rule,
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: Vec::new(),
+        };
+
+        universe.reset();
+        universe
+
+    }
+
+    fn reset(&mut self) {
+
+        self.nodes.clear();
+        self.index.clear();
+        self.results.clear();
+        self.empty.clear();
+
+        for population in 0..2 {
+            self.nodes.push(Node { level: 0, nw: DEAD, ne: DEAD, sw: DEAD, se: DEAD,
+                                   population });
+        }
+
+        self.empty.push(DEAD);
+
+    }
+
+    #[inline]
+    pub fn population(&self, node: NodeId) -> u64 {
+        self.nodes[node].population
+    }
+
+    #[inline]
+    pub fn level(&self, node: NodeId) -> usize {
+        self.nodes[node].level
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+
+        // find or create node with the given children
+
+        if let Some(&node) = self.index.get(&(nw, ne, sw, se)) {
+            return node;
+        }
+
+        let population = self.nodes[nw].population + self.nodes[ne].population +
+                         self.nodes[sw].population + self.nodes[se].population;
+
+        let node = self.nodes.len();
+
+        self.nodes.push(Node {
+            level: self.nodes[nw].level + 1,
+            nw, ne, sw, se,
+            population,
+        });
+
+        self.index.insert((nw, ne, sw, se), node);
+
+        node
+
+    }
+
+    fn empty(&mut self, level: usize) -> NodeId {
+
+        while self.empty.len() <= level {
+            let child = *self.empty.last().unwrap();
+            let node = self.join(child, child, child, child);
+            self.empty.push(node);
+        }
+
+        self.empty[level]
+
+    }
+
+    fn expand(&mut self, node: NodeId) -> NodeId {
+
+        // returns node of the next level with the given node in its center
+
+        let level = self.level(node);
+        let e = self.empty(level - 1);
+
+        let (nw, ne, sw, se) = self.children(node);
+
+        let new_nw = self.join(e, e, e, nw);
+        let new_ne = self.join(e, e, ne, e);
+        let new_sw = self.join(e, sw, e, e);
+        let new_se = self.join(se, e, e, e);
+
+        self.join(new_nw, new_ne, new_sw, new_se)
+
+    }
+
+    #[inline]
+    fn children(&self, node: NodeId) -> (NodeId, NodeId, NodeId, NodeId) {
+        let n = &self.nodes[node];
+        (n.nw, n.ne, n.sw, n.se)
+    }
+
+    fn center(&mut self, node: NodeId) -> NodeId {
+
+        // node of the previous level from the center of the given node
+
+        let (nw, ne, sw, se) = self.children(node);
+
+        let nw_se = self.nodes[nw].se;
+        let ne_sw = self.nodes[ne].sw;
+        let sw_ne = self.nodes[sw].ne;
+        let se_nw = self.nodes[se].nw;
+
+        self.join(nw_se, ne_sw, sw_ne, se_nw)
+
+    }
+
+    fn is_padded(&mut self, node: NodeId) -> bool {
+
+        // all live cells of the node are inside its central quarter,
+        // so they can't escape the result of `successor`
+
+        let center = self.center(node);
+        let center = self.center(center);
+
+        self.population(node) == self.population(center)
+
+    }
+
+    fn successor(&mut self, node: NodeId, step_log: usize) -> NodeId {
+
+        // returns the center of a node advanced by 2^step_log generations,
+        // resulting node has a level one less than the given one
+
+        let level = self.level(node);
+
+        debug_assert!(level >= 2 && step_log <= level - 2);
+
+        if self.population(node) == 0 {
+            return self.empty(level - 1);
+        }
+
+        if let Some(&result) = self.results.get(&(node, step_log)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.base_case(node)
+        } else {
+
+            // split the node into 9 overlapping subnodes of the previous level
+
+            let (nw, ne, sw, se) = self.children(node);
+            let (_, nw_ne, nw_sw, nw_se) = self.children(nw);
+            let (ne_nw, _, ne_sw, ne_se) = self.children(ne);
+            let (sw_nw, sw_ne, _, sw_se) = self.children(sw);
+            let (se_nw, se_ne, se_sw, _) = self.children(se);
+
+            let n01 = self.join(nw_ne, ne_nw, nw_se, ne_sw);
+            let n10 = self.join(nw_sw, nw_se, sw_nw, sw_ne);
+            let n11 = self.join(nw_se, ne_sw, sw_ne, se_nw);
+            let n12 = self.join(ne_sw, ne_se, se_nw, se_ne);
+            let n21 = self.join(sw_ne, se_nw, sw_se, se_sw);
+
+            let subnodes = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+            let mut s = [DEAD; 9];
+
+            if step_log == level - 2 {
+                // full speed: advance subnodes by a half of the step first
+                for (idx, &subnode) in subnodes.iter().enumerate() {
+                    s[idx] = self.successor(subnode, step_log - 1);
+                }
+            } else {
+                // slow mode: subnodes are not advanced, only their centers are taken
+                for (idx, &subnode) in subnodes.iter().enumerate() {
+                    s[idx] = self.center(subnode);
+                }
+            }
+
+            let rest_log = if step_log == level - 2 { step_log - 1 } else { step_log };
+
+            let q_nw = self.join(s[0], s[1], s[3], s[4]);
+            let q_ne = self.join(s[1], s[2], s[4], s[5]);
+            let q_sw = self.join(s[3], s[4], s[6], s[7]);
+            let q_se = self.join(s[4], s[5], s[7], s[8]);
+
+            let r_nw = self.successor(q_nw, rest_log);
+            let r_ne = self.successor(q_ne, rest_log);
+            let r_sw = self.successor(q_sw, rest_log);
+            let r_se = self.successor(q_se, rest_log);
+
+            self.join(r_nw, r_ne, r_sw, r_se)
+        };
+
+        self.results.insert((node, step_log), result);
+
+        result
+
+    }
+
+    fn base_case(&mut self, node: NodeId) -> NodeId {
+
+        // advance 4x4 node by one generation using game rules directly
+
+        let mut grid = [[false; 4]; 4];
+
+        for (row, cells) in grid.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = self.get_cell(node, col as isize, row as isize);
+            }
+        }
+
+        let mut next = [DEAD; 4];
+
+        for (idx, &(col, row)) in [(1, 1), (2, 1), (1, 2), (2, 2)].iter().enumerate() {
+
+            let mut neighbours_cnt = 0;
+
+            for d_row in 0..3 {
+                for d_col in 0..3 {
+                    if (d_row != 1 || d_col != 1) && grid[row + d_row - 1][col + d_col - 1] {
+                        neighbours_cnt += 1;
+                    }
+                }
+            }
+
+            let is_alive = if grid[row][col] {
+                self.rule.survives(neighbours_cnt)
+            } else {
+                self.rule.is_born(neighbours_cnt)
+            };
+
+            next[idx] = if is_alive { ALIVE } else { DEAD };
+        }
+
+        self.join(next[0], next[1], next[2], next[3])
+
+    }
+
+    fn get_cell(&self, node: NodeId, col: isize, row: isize) -> bool {
+
+        // coordinates are relative to the top left corner of the node
+
+        let n = &self.nodes[node];
+
+        if n.level == 0 {
+            return node == ALIVE;
+        }
+
+        if n.population == 0 {
+            return false;
+        }
+
+        let half = 1 << (n.level - 1);
+
+        match (col >= half, row >= half) {
+            (false, false) => self.get_cell(n.nw, col, row),
+            (true, false) => self.get_cell(n.ne, col - half, row),
+            (false, true) => self.get_cell(n.sw, col, row - half),
+            (true, true) => self.get_cell(n.se, col - half, row - half),
+        }
+
+    }
+
+    fn build(&mut self, level: usize, cells: &[Coord], col: isize, row: isize) -> NodeId {
+
+        // build node of a given level covering a square with top left corner
+        // at (col, row) from cells that lie inside that square
+
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1 << (level - 1);
+
+        let mut quadrants: [Vec<Coord>; 4] = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+
+        for &coord in cells {
+            let idx = ((coord.row >= row + half) as usize) * 2 + (coord.col >= col + half) as usize;
+            quadrants[idx].push(coord);
+        }
+
+        let nw = self.build(level - 1, &quadrants[0], col, row);
+        let ne = self.build(level - 1, &quadrants[1], col + half, row);
+        let sw = self.build(level - 1, &quadrants[2], col, row + half);
+        let se = self.build(level - 1, &quadrants[3], col + half, row + half);
+
+        self.join(nw, ne, sw, se)
+
+    }
+
+    pub fn build_root(&mut self, coords: &[Coord]) -> Option<NodeId> {
+
+        // find the smallest level whose square centered at (0, 0) contains all cells,
+        // None if cells are too far from the origin for any root
+
+        let max_abs = coords.iter().map(|c| c.col.unsigned_abs().max(c.row.unsigned_abs())).max().unwrap_or(0);
+
+        let mut level = 3;
+        while (1usize << (level - 1)) <= max_abs {
+            if level == MAX_ROOT_LEVEL {
+                return None;
+            }
+            level += 1;
+        }
+
+        let half = 1 << (level - 1);
+
+        Some(self.build(level, coords, -half, -half))
+
+    }
+
+    fn collect(&self, node: NodeId, col: isize, row: isize, coords: &mut Vec<Coord>) {
+
+        // collect live cells of a node with top left corner at (col, row)
+
+        let n = &self.nodes[node];
+
+        if n.population == 0 {
+            return;
+        }
+
+        if n.level == 0 {
+            coords.push(Coord { col, row });
+            return;
+        }
+
+        let half = 1 << (n.level - 1);
+
+        self.collect(n.nw, col, row, coords);
+        self.collect(n.ne, col + half, row, coords);
+        self.collect(n.sw, col, row + half, coords);
+        self.collect(n.se, col + half, row + half, coords);
+
+    }
+
+    pub fn to_coords(&self, root: NodeId) -> Vec<Coord> {
+
+        let mut coords = Vec::with_capacity(self.population(root) as usize);
+
+        let level = self.level(root);
+        let half = if level == 0 { 0 } else { 1 << (level - 1) };
+
+        self.collect(root, -half, -half, &mut coords);
+
+        coords
+
+    }
+
+    pub fn advance(&mut self, root: NodeId, generations: u64) -> NodeId {
+
+        // advance universe by any number of generations splitting it
+        // into steps of 2^k generations
+
+        let mut root = root;
+        let mut remaining = generations;
+
+        while remaining > 0 {
+
+            if self.nodes.len() > MAX_NODES {
+                // free memory by dropping all unused nodes and memoized results
+                let coords = self.to_coords(root);
+                self.reset();
+                // cells of the current root always fit into a new one
+                root = self.build_root(&coords).unwrap();
+            }
+
+            let step_log = 63 - remaining.leading_zeros() as usize;
+
+            // root must be large enough to contain the result, live cells move
+            // with the speed of light at most, so one more level is enough
+            while self.level(root) < step_log + 3 || !self.is_padded(root) {
+                root = self.expand(root);
+            }
+
+            root = self.successor(root, step_log);
+            remaining -= 1 << step_log;
+        }
+
+        root
+
+    }
+
+}
+
+
+#[test]
+fn test_hashlife_build() {
+    let coords = vec![Coord { col: -5, row: 3 }, Coord { col: 0, row: 0 }, Coord { col: 7, row: -8 }];
+
+    let mut universe = HashLife::new(Rule::default());
+    let root = universe.build_root(&coords).unwrap();
+
+    assert_eq!(universe.population(root), 3);
+
+    let mut result = universe.to_coords(root);
+    let mut expected = coords.clone();
+
+    result.sort();
+    expected.sort();
+
+    assert_eq!(result, expected);
+
+    // cells too far from the origin don't fit into any root
+    assert!(universe.build_root(&[Coord { col: isize::MIN, row: 0 }]).is_none());
+    assert!(universe.build_root(&[Coord { col: 0, row: isize::MAX }]).is_none());
+}
+
+#[test]
+fn test_hashlife_glider() {
+    // glider moves by one cell diagonally every 4 generations
+    let glider = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                      Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }];
+
+    for &generations in [1u64, 4, 100, 1024, 1000000].iter() {
+
+        let mut universe = HashLife::new(Rule::default());
+        let root = universe.build_root(&glider).unwrap();
+        let root = universe.advance(root, generations);
+
+        let shift = (generations / 4) as isize;
+
+        let mut result = universe.to_coords(root);
+        let mut expected: Vec<Coord> = if generations % 4 == 0 {
+            glider.iter().map(|c| Coord { col: c.col + shift, row: c.row + shift }).collect()
+        } else {
+            // first phase of a glider
+            vec![Coord { col: 0, row: 1 }, Coord { col: 2, row: 1 }, Coord { col: 1, row: 2 },
+                 Coord { col: 2, row: 2 }, Coord { col: 1, row: 3 }]
+        };
+
+        result.sort();
+        expected.sort();
+
+        assert_eq!(result, expected);
+    }
+}
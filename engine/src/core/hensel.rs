@@ -0,0 +1,241 @@
+//! Isotropic non-totalistic rules in Hensel notation.
+//!
+//! Letters after a neighbours count choose configurations of live
+//! neighbours up to rotations and reflections, e.g. "2a" means two live
+//! neighbours next to each other and "2-a" any two neighbours except
+//! those. A count without letters means all its configurations.
+//!
+//! Conditions are kept as a lookup table indexed by the bit mask of live
+//! neighbours in the order of `Board::get_vicinity`.
+
+// letters in canonical order and a representative configuration of each,
+// bit k is the k-th neighbour clockwise starting from the north one,
+// configurations of 5 to 7 neighbours are complements of 3 to 1 ones
+const LETTERS: [&[(char, u8)]; 5] = [
+    &[],
+    &[('c', 0b00000010), ('e', 0b00000001)],
+    &[('c', 0b00001010), ('e', 0b00000101), ('a', 0b00000011), ('i', 0b00010001),
+      ('k', 0b00001001), ('n', 0b00100010)],
+    &[('c', 0b00101010), ('e', 0b00010101), ('a', 0b00000111), ('i', 0b10000011),
+      ('k', 0b00100101), ('n', 0b00001011), ('j', 0b01000011), ('q', 0b00100011),
+      ('r', 0b00010011), ('y', 0b00101001)],
+    &[('c', 0b10101010), ('e', 0b01010101), ('a', 0b00001111), ('i', 0b00011011),
+      ('k', 0b01001011), ('n', 0b10001011), ('j', 0b01010011), ('q', 0b00100111),
+      ('r', 0b00010111), ('y', 0b00101011), ('t', 0b00111001), ('w', 0b01100011),
+      ('z', 0b00110011)],
+];
+
+// number of neighbour configurations
+pub const MASKS: usize = 256;
+
+
+fn letters(count: usize) -> Vec<(char, u8)> {
+
+    // letters of the given neighbours count with their representatives
+
+    if count <= 4 {
+        LETTERS[count].to_vec()
+    } else {
+        LETTERS[8 - count].iter().map(|&(letter, ring)| (letter, !ring)).collect()
+    }
+}
+
+fn symmetries(ring: u8) -> Vec<u8> {
+
+    // all rotations and reflections of a configuration
+
+    let mut result = Vec::new();
+
+    let mut reflected: u8 = 0;
+    for k in 0..8 {
+        if ring & (1 << k) != 0 {
+            reflected |= 1 << ((8 - k) % 8);
+        }
+    }
+
+    for &config in [ring, reflected].iter() {
+        for quarter in 0..4 {
+            result.push(config.rotate_left(2 * quarter));
+        }
+    }
+
+    result
+}
+
+#[inline]
+fn to_mask(ring: u8) -> usize {
+    // Board::get_vicinity starts from the west neighbour, which is 6th
+    // clockwise from the north one
+    ring.rotate_left(2) as usize
+}
+
+pub fn vicinity_mask(vicinity: &[bool]) -> usize {
+    vicinity.iter().enumerate().filter(|&(_, &alive)| alive).map(|(k, _)| 1 << k).sum()
+}
+
+fn set_letter(table: &mut [bool], ring: u8, value: bool) {
+    for config in symmetries(ring) {
+        table[to_mask(config)] = value;
+    }
+}
+
+pub fn parse_conditions(conditions: &str) -> Option<Vec<bool>> {
+
+    // parses conditions like "2-a3" into the table of neighbour masks,
+    // returns None if a letter is not valid for its count
+
+    let mut table = vec![false; MASKS];
+
+    let mut count: Option<usize> = None;
+    let mut negated = false;
+    let mut has_letters = false;
+
+    // a count without letters means all its configurations,
+    // negated letters are removed from all configurations
+    fn set_count(table: &mut [bool], count: usize) {
+        for (mask, allowed) in table.iter_mut().enumerate().take(MASKS) {
+            if mask.count_ones() as usize == count {
+                *allowed = true;
+            }
+        }
+    }
+
+    // None marks the end of conditions
+    for c in conditions.chars().map(Some).chain(Some(None)) {
+
+        let c = match c {
+            Some(c) if !c.is_ascii_digit() => c,
+            _ => {
+
+                if negated && !has_letters {
+                    // "2-" without letters
+                    return None;
+                }
+
+                if let (Some(count), false) = (count, has_letters) {
+                    set_count(&mut table, count);
+                }
+
+                count = match c.and_then(|c| c.to_digit(10)) {
+                    Some(n) if n <= 8 => Some(n as usize),
+                    Some(_) => return None,
+                    None => None,
+                };
+                negated = false;
+                has_letters = false;
+                continue;
+            }
+        };
+
+        let cur_count = count?;
+
+        if c == '-' {
+            if negated || has_letters {
+                return None;
+            }
+            negated = true;
+            set_count(&mut table, cur_count);
+            continue;
+        }
+
+        let letter = c.to_ascii_lowercase();
+
+        match letters(cur_count).into_iter().find(|&(x, _)| x == letter) {
+            Some((_, ring)) => set_letter(&mut table, ring, !negated),
+            None => return None,
+        }
+
+        has_letters = true;
+    }
+
+    Some(table)
+}
+
+pub fn is_totalistic(table: &[bool]) -> bool {
+    // true if conditions depend only on the number of live neighbours
+    (0..MASKS).all(|mask| table[mask] == table[(1 << mask.count_ones()) - 1])
+}
+
+pub fn format_conditions(table: &[bool]) -> String {
+
+    // canonical form of conditions, letters are negated
+    // when more than half of them are chosen
+
+    let mut result = String::new();
+
+    for count in 0..9 {
+
+        let letters = letters(count);
+
+        let chosen: Vec<char> = letters.iter().filter(|&&(_, ring)| table[to_mask(ring)]).
+            map(|&(letter, _)| letter).collect();
+
+        let missing: Vec<char> = letters.iter().filter(|&&(_, ring)| !table[to_mask(ring)]).
+            map(|&(letter, _)| letter).collect();
+
+        if letters.is_empty() {
+            // 0 and 8 neighbours have a single configuration
+            if table[(1 << count) - 1] {
+                result.push_str(&count.to_string());
+            }
+        } else if missing.is_empty() {
+            result.push_str(&count.to_string());
+        } else if !chosen.is_empty() {
+            result.push_str(&count.to_string());
+            if chosen.len() > missing.len() {
+                result.push('-');
+                result.extend(missing);
+            } else {
+                result.extend(chosen);
+            }
+        }
+    }
+
+    result
+}
+
+
+#[test]
+fn test_letters_cover_all_configurations() {
+    use std::collections::HashSet;
+
+    // configurations of different letters never overlap and
+    // all letters of a count cover all its configurations
+    for count in 1..8usize {
+        let mut covered: HashSet<u8> = HashSet::new();
+
+        for (_, ring) in letters(count) {
+            assert_eq!(ring.count_ones() as usize, count);
+
+            let orbit: HashSet<u8> = symmetries(ring).into_iter().collect();
+            assert!(orbit.is_disjoint(&covered));
+            covered.extend(orbit);
+        }
+
+        assert_eq!(covered.len(), (0..MASKS).filter(|x| x.count_ones() as usize == count).count());
+    }
+}
+
+#[test]
+fn test_parse_conditions() {
+    // two adjacent neighbours, e.g. north and north-east ones
+    let table = parse_conditions("2a").unwrap();
+    assert!(table[vicinity_mask(&[false, false, true, true, false, false, false, false])]);
+    assert!(!table[vicinity_mask(&[false, false, true, false, false, false, true, false])]);
+
+    let table = parse_conditions("2-a").unwrap();
+    assert!(!table[vicinity_mask(&[false, false, true, true, false, false, false, false])]);
+    assert!(table[vicinity_mask(&[false, false, true, false, false, false, true, false])]);
+
+    assert!(is_totalistic(&parse_conditions("23").unwrap()));
+    assert!(!is_totalistic(&parse_conditions("2-i34q").unwrap()));
+
+    assert_eq!(format_conditions(&parse_conditions("2-i34q").unwrap()), "2-i34q");
+    assert_eq!(format_conditions(&parse_conditions("12ceaikn").unwrap()), "12");
+    assert_eq!(format_conditions(&parse_conditions("4-cekainyqjr").unwrap()), "4twz");
+
+    assert!(parse_conditions("1a").is_none());
+    assert!(parse_conditions("0c").is_none());
+    assert!(parse_conditions("2-").is_none());
+    assert!(parse_conditions("a").is_none());
+}
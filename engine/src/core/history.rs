@@ -0,0 +1,159 @@
+//! Undo/redo history of board edits.
+//!
+//! Two kinds of edits are recorded: changes of single cells, e.g. drawn
+//! with a mouse, and replacements of the whole board, e.g. clearing it or
+//! pasting a pattern. Edits of cells made between `begin_group` and
+//! `end_group` are undone at once.
+
+use board::{Cell, Coord, Grid};
+use core::Rule;
+use core::loader::Metadata;
+
+// oldest edits are forgotten when there are more of them
+const MAX_EDITS: usize = 100;
+
+
+/// State of the board saved before and after its replacement
+#[derive(Clone)]
+pub struct Snapshot {
+    // live and dying cells
+    pub cells: Vec<(Coord, Cell)>,
+    pub iteration: usize,
+    pub rule: Rule,
+    pub grid: Grid,
+    pub metadata: Metadata,
+}
+
+#[derive(Clone)]
+pub enum Edit {
+    // cell coordinates and its contents before and after the edit
+    Cells(Vec<(Coord, Cell, Cell)>),
+    // snapshots are boxed, so edits of cells stay small
+    Board { before: Box<Snapshot>, after: Box<Snapshot> },
+}
+
+impl Edit {
+
+    fn reversed(self) -> Edit {
+        match self {
+            Edit::Cells(changes) => Edit::Cells(changes.into_iter().rev().
+                                                map(|(coord, before, after)| (coord, after, before)).collect()),
+            Edit::Board { before, after } => Edit::Board { before: after, after: before },
+        }
+    }
+
+}
+
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+
+    // edits of cells are merged into the last edit while group is open
+    group_open: bool,
+}
+
+impl History {
+
+    pub fn new() -> Self {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            group_open: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group_open = false;
+    }
+
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.undo.push(Edit::Cells(Vec::new()));
+        self.group_open = true;
+    }
+
+    pub fn end_group(&mut self) {
+
+        if !self.group_open {
+            return;
+        }
+
+        self.group_open = false;
+
+        // nothing was changed inside the group
+        let is_empty = match self.undo.last() {
+            Some(Edit::Cells(changes)) => changes.is_empty(),
+            _ => false
+        };
+
+        if is_empty {
+            self.undo.pop();
+        }
+    }
+
+    pub fn push(&mut self, edit: Edit) {
+
+        // any new edit makes redo impossible
+        self.redo.clear();
+
+        if self.group_open {
+            if let Edit::Cells(changes) = edit {
+                if let Some(&mut Edit::Cells(ref mut group)) = self.undo.last_mut() {
+                    group.extend(changes);
+                }
+                return;
+            }
+            // board replacement closes the group
+            self.end_group();
+        }
+
+        self.undo.push(edit);
+
+        if self.undo.len() > MAX_EDITS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<Edit> {
+
+        // returns the edit that reverts the last one
+
+        self.end_group();
+
+        match self.undo.pop() {
+            Some(edit) => {
+                self.redo.push(edit.clone());
+                Some(edit.reversed())
+            },
+            None => None
+        }
+    }
+
+    pub fn redo(&mut self) -> Option<Edit> {
+
+        // returns the edit that was reverted last
+
+        self.end_group();
+
+        match self.redo.pop() {
+            Some(edit) => {
+                self.undo.push(edit.clone());
+                Some(edit)
+            },
+            None => None
+        }
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use super::{Metadata, ParseError, Pattern, parse_rule, format_rule, parse_coords, column_of};
+use super::super::{Coord, Rule};
+use board::{Board, CellDesc};
+
+
+pub const HEADER: &str = "#Life 1.05";
+
+// blocks are split into strips so that lines don't exceed the length
+// recommended by the format description
+const MAX_LINE_LENGTH: isize = 80;
+
+
+pub fn parse_stream<T>(mut lines: T) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    // accepted file format described here:
+    // http://www.conwaylife.com/wiki/Life_1.05
+    // #D - description, #N - Conway's rule, #R - rule in S/B notation,
+    // #P - position of the top left corner of the following block of cells
+
+    let mut metadata = Metadata::default();
+    let mut rule = None;
+    let mut grid = None;
+
+    let mut coords = Vec::new();
+
+    // top left corner of the current block and its current row,
+    // cells before the first #P line start at the origin
+    let mut corner = Coord { col: 0, row: 0 };
+    let mut row = 0;
+
+    let mut line_no = 0;
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        if line.starts_with('#') {
+
+            let text = line.get(2..).unwrap_or("").trim();
+
+            match line.get(..2) {
+                Some("#D") | Some("#C") => metadata.push_description(text),
+                Some("#N") => rule = Some(Rule::default()),
+                Some("#R") => {
+                    let (parsed_rule, parsed_grid) = parse_rule(text, line_no, column_of(&line, text))?;
+                    rule = Some(parsed_rule);
+                    grid = parsed_grid;
+                },
+                Some("#P") => {
+                    corner = parse_coords(&line, text, line_no)?;
+                    row = 0;
+                },
+                // #Life header and unknown lines
+                _ => {},
+            }
+
+            continue;
+        }
+
+        for (idx, c) in line.trim_end().chars().enumerate() {
+            match c {
+                '.' => {},
+                '*' | 'O' => coords.push(Coord { col: corner.col + idx as isize, row: corner.row + row }),
+                _ => return Err(ParseError::UnknownCell(c, line_no, idx + 1)),
+            }
+        }
+
+        row += 1;
+
+    }
+
+    let states = vec![1; coords.len()];
+
+    Ok(Pattern::from_absolute(coords, states, rule, grid, metadata))
+
+}
+
+pub fn encode(board: &Board, rule: &Rule, metadata: &Metadata) -> String {
+
+    // only live cells are saved, the board is cut into vertical strips
+    // and every strip with live cells becomes a block, rows of a block
+    // are written up to their last live cell
+
+    let mut strips: BTreeMap<isize, BTreeMap<isize, Vec<isize>>> = BTreeMap::new();
+
+    for CellDesc { coord, state, .. } in board.iter() {
+        if state == 1 {
+            let strip = coord.col.div_euclid(MAX_LINE_LENGTH) * MAX_LINE_LENGTH;
+            strips.entry(strip).or_default().entry(coord.row).or_default().push(coord.col - strip);
+        }
+    }
+
+    let mut result = format!("{}\n", HEADER);
+
+    for line in metadata.descriptions() {
+        result.push_str(&format!("#D {}\n", line));
+    }
+
+    let grid = board.get_grid();
+
+    if *rule == Rule::default() && !grid.is_bounded() {
+        result.push_str("#N\n");
+    } else {
+        result.push_str(&format!("#R {}\n", format_rule(rule, grid)));
+    }
+
+    for (strip, rows) in strips {
+
+        let first_row = *rows.keys().next().unwrap();
+        let last_row = *rows.keys().next_back().unwrap();
+
+        result.push_str(&format!("#P {} {}\n", strip, first_row));
+
+        for row in first_row..=last_row {
+
+            let line = match rows.get(&row) {
+                Some(cols) => {
+                    let mut line = vec!['.'; *cols.iter().max().unwrap() as usize + 1];
+                    for &col in cols {
+                        line[col as usize] = '*';
+                    }
+                    line.into_iter().collect()
+                },
+                None => String::from("."),
+            };
+
+            result.push_str(&line);
+            result.push('\n');
+        }
+
+    }
+
+    result
+
+}
@@ -0,0 +1,60 @@
+use super::{Metadata, ParseError, Pattern, parse_coords};
+use super::super::Coord;
+use board::{Board, CellDesc};
+
+
+pub const HEADER: &str = "#Life 1.06";
+
+
+pub fn parse_stream<T>(mut lines: T) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    // accepted file format described here:
+    // http://www.conwaylife.com/wiki/Life_1.06
+    // every line has column and row of a live cell separated by spaces,
+    // lines starting with # are ignored
+
+    let mut coords = Vec::new();
+
+    let mut line_no = 0;
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        let text = line.trim();
+
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        coords.push(parse_coords(&line, text, line_no)?);
+
+    }
+
+    let states = vec![1; coords.len()];
+
+    Ok(Pattern::from_absolute(coords, states, None, None, Metadata::default()))
+
+}
+
+pub fn encode(board: &Board) -> String {
+
+    // only live cells are saved, neither the rule nor metadata
+    // can be kept in this format
+
+    let mut coords: Vec<Coord> = board.iter().
+        filter(|desc| desc.state == 1).
+        map(|CellDesc { coord, .. }| coord).collect();
+
+    coords.sort_by_key(|c| (c.row, c.col));
+
+    let mut result = format!("{}\n", HEADER);
+
+    for Coord { col, row } in coords {
+        result.push_str(&format!("{} {}\n", col, row));
+    }
+
+    result
+
+}
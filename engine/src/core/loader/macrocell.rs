@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use super::{Metadata, ParseError, Pattern, MAX_CELLS, parse_rule, format_rule, column_of};
+use super::super::{Coord, Rule};
+use core::table::MAX_STATES;
+use board::{Board, CellDesc};
+
+
+pub const HEADER: &str = "[M2]";
+
+// leaves of two state patterns are 8x8 squares
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: isize = 1 << LEAF_LEVEL;
+
+// larger nodes would overflow coordinates
+const MAX_LEVEL: u32 = 62;
+
+
+enum Node {
+    // live cells of an 8x8 square
+    Leaf(Vec<Coord>),
+    // states of a 2x2 square of multi-state patterns
+    Cells([usize; 4]),
+    // level and indices of nw, ne, sw and se quarters, 0 is an empty quarter
+    Inner(u32, [usize; 4]),
+}
+
+impl Node {
+
+    fn level(&self) -> u32 {
+        match *self {
+            Node::Leaf(_) => LEAF_LEVEL,
+            Node::Cells(_) => 1,
+            Node::Inner(level, _) => level,
+        }
+    }
+
+    fn population(&self, populations: &[usize]) -> usize {
+
+        // populations of previous nodes are known, huge ones saturate
+
+        match *self {
+            Node::Leaf(ref cells) => cells.len(),
+            Node::Cells(ref states) => states.iter().filter(|&&state| state > 0).count(),
+            Node::Inner(_, ref children) => children.iter().
+                filter(|&&child| child > 0).
+                fold(0, |total: usize, &child| total.saturating_add(populations[child - 1])),
+        }
+
+    }
+
+}
+
+fn parse_leaf(line: &str, line_no: usize) -> Result<Node, ParseError> {
+
+    // rows of a leaf end with $, dead cells at the end of a row
+    // and empty rows at the end of a leaf are omitted
+
+    let text = line.trim();
+    let first_col = column_of(line, text);
+
+    let mut cells = Vec::new();
+    let mut row = 0;
+    let mut col = 0;
+
+    for (idx, c) in text.chars().enumerate() {
+        match c {
+            '.' => col += 1,
+            '*' => {
+                cells.push(Coord { col, row });
+                col += 1;
+            },
+            '$' => {
+                row += 1;
+                col = 0;
+            },
+            _ => return Err(ParseError::UnknownCell(c, line_no, first_col + idx)),
+        }
+
+        if col > LEAF_SIZE || row > LEAF_SIZE || (row == LEAF_SIZE && c != '$') {
+            return Err(ParseError::UnexpectedSymbol(c.to_string(), line_no, first_col + idx));
+        }
+    }
+
+    Ok(Node::Leaf(cells))
+
+}
+
+fn parse_node(line: &str, line_no: usize, nodes: &[Node], states: usize) -> Result<Node, ParseError> {
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut numbers = Vec::new();
+
+    for token in tokens.iter() {
+        match token.parse::<usize>() {
+            Ok(number) => numbers.push(number),
+            Err(_) => return Err(ParseError::NotANumber(token.to_string(), line_no, column_of(line, token))),
+        }
+    }
+
+    if numbers.len() != 5 {
+        return Err(ParseError::UnexpectedSymbol(line.trim().to_string(), line_no, column_of(line, tokens[0])));
+    }
+
+    let level = numbers[0] as u32;
+    let children = [numbers[1], numbers[2], numbers[3], numbers[4]];
+
+    if level == 1 {
+        // states of cells are limited by the rule
+        if let Some(idx) = children.iter().position(|&state| state >= states) {
+            let token = tokens[idx + 1];
+            return Err(ParseError::UnexpectedSymbol(token.to_string(), line_no, column_of(line, token)));
+        }
+        return Ok(Node::Cells(children));
+    }
+
+    if !(2..=MAX_LEVEL).contains(&level) {
+        return Err(ParseError::UnexpectedSymbol(tokens[0].to_string(), line_no, column_of(line, tokens[0])));
+    }
+
+    // nodes refer only to the nodes of the previous level read before them,
+    // indices start from 1
+    for (idx, &child) in children.iter().enumerate() {
+        if child > nodes.len() || (child > 0 && nodes[child - 1].level() != level - 1) {
+            let token = tokens[idx + 1];
+            return Err(ParseError::UnexpectedSymbol(token.to_string(), line_no, column_of(line, token)));
+        }
+    }
+
+    Ok(Node::Inner(level, children))
+
+}
+
+fn expand(nodes: &[Node], populations: &[usize], idx: usize, top_left: Coord,
+          coords: &mut Vec<Coord>, states: &mut Vec<usize>) {
+
+    // empty nodes are skipped, otherwise a chain of them could be
+    // visited exponentially many times
+
+    if idx == 0 || populations[idx - 1] == 0 {
+        return;
+    }
+
+    match nodes[idx - 1] {
+        Node::Leaf(ref cells) => {
+            for cell in cells {
+                coords.push(Coord { col: top_left.col + cell.col, row: top_left.row + cell.row });
+                states.push(1);
+            }
+        },
+        Node::Cells(ref cell_states) => {
+            for (quarter, &state) in cell_states.iter().enumerate() {
+                if state > 0 {
+                    coords.push(Coord { col: top_left.col + (quarter % 2) as isize,
+                                        row: top_left.row + (quarter / 2) as isize });
+                    states.push(state);
+                }
+            }
+        },
+        Node::Inner(level, ref children) => {
+            let half = 1 << (level - 1);
+            for (quarter, &child) in children.iter().enumerate() {
+                let corner = Coord { col: top_left.col + half * (quarter % 2) as isize,
+                                     row: top_left.row + half * (quarter / 2) as isize };
+                expand(nodes, populations, child, corner, coords, states);
+            }
+        },
+    }
+
+}
+
+pub fn parse_stream<T>(mut lines: T) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    // accepted file format described here:
+    // http://golly.sourceforge.net/Help/formats.html#mc
+    // the last node is the root, its center is at the origin
+
+    let mut metadata = Metadata::default();
+    let mut rule = None;
+    let mut grid = None;
+
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut populations: Vec<usize> = Vec::new();
+    // position of the last node which is the root
+    let mut root_pos = (0, 0);
+
+    let mut line_no = 0;
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        let text = line.trim();
+
+        if text.is_empty() || text.starts_with('[') {
+            continue;
+        }
+
+        if text.starts_with('#') {
+
+            let value = text.get(2..).unwrap_or("").trim();
+
+            match text.get(..2) {
+                Some("#N") => metadata.name = Some(String::from(value)),
+                Some("#O") => metadata.author = Some(String::from(value)),
+                Some("#C") | Some("#D") => metadata.comments.push(String::from(value)),
+                Some("#R") => {
+                    let (parsed_rule, parsed_grid) = parse_rule(value, line_no, column_of(&line, value))?;
+                    rule = Some(parsed_rule);
+                    grid = parsed_grid;
+                },
+                // #G with the generation number and unknown lines
+                _ => {},
+            }
+
+            continue;
+        }
+
+        let node = if text.starts_with(|c: char| c.is_ascii_digit()) {
+            let states = rule.as_ref().map_or(MAX_STATES, Rule::get_states);
+            parse_node(&line, line_no, &nodes, states)?
+        } else {
+            parse_leaf(&line, line_no)?
+        };
+
+        populations.push(node.population(&populations));
+        nodes.push(node);
+        root_pos = (line_no, column_of(&line, text));
+    }
+
+    let mut coords = Vec::new();
+    let mut states = Vec::new();
+
+    if let Some(root) = nodes.last() {
+
+        // a few lines may describe a huge number of cells
+
+        if populations[nodes.len() - 1] > MAX_CELLS {
+            return Err(ParseError::TooManyCells(root_pos.0, root_pos.1));
+        }
+
+        let half = 1 << (root.level() - 1);
+        expand(&nodes, &populations, nodes.len(), Coord { col: -half, row: -half }, &mut coords, &mut states);
+    }
+
+    Ok(Pattern::from_absolute(coords, states, rule, grid, metadata))
+
+}
+
+
+struct Encoder {
+    lines: Vec<String>,
+    // identical nodes are written only once
+    indices: HashMap<String, usize>,
+    multistate: bool,
+}
+
+impl Encoder {
+
+    fn add(&mut self, line: String) -> usize {
+
+        if let Some(&idx) = self.indices.get(&line) {
+            return idx;
+        }
+
+        self.lines.push(line.clone());
+        self.indices.insert(line, self.lines.len());
+
+        self.lines.len()
+
+    }
+
+    fn leaf(&mut self, top_left: Coord, cells: &[(Coord, usize)]) -> usize {
+
+        let mut rows = vec![Vec::new(); LEAF_SIZE as usize];
+
+        for &(coord, _) in cells {
+            rows[(coord.row - top_left.row) as usize].push(coord.col - top_left.col);
+        }
+
+        let last_row = rows.iter().rposition(|cols| !cols.is_empty()).unwrap_or(0);
+        let mut line = String::new();
+
+        for mut cols in rows.into_iter().take(last_row + 1) {
+            cols.sort();
+            let mut col = 0;
+            for cur in cols {
+                line.extend((col..cur).map(|_| '.'));
+                line.push('*');
+                col = cur + 1;
+            }
+            line.push('$');
+        }
+
+        self.add(line)
+
+    }
+
+    fn node(&mut self, level: u32, top_left: Coord, cells: Vec<(Coord, usize)>) -> usize {
+
+        if cells.is_empty() {
+            return 0;
+        }
+
+        if level == LEAF_LEVEL && !self.multistate {
+            return self.leaf(top_left, &cells);
+        }
+
+        let half = 1 << (level - 1);
+        let quarter = |coord: Coord| ((coord.col - top_left.col) / half + 2 * ((coord.row - top_left.row) / half)) as usize;
+
+        if level == 1 {
+            let mut states = [0; 4];
+            for (coord, state) in cells {
+                states[quarter(coord)] = state;
+            }
+            return self.add(format!("1 {} {} {} {}", states[0], states[1], states[2], states[3]));
+        }
+
+        let mut quarters = vec![Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+
+        for (coord, state) in cells {
+            quarters[quarter(coord)].push((coord, state));
+        }
+
+        let mut children = [0; 4];
+
+        for (idx, quarter_cells) in quarters.into_iter().enumerate() {
+            let corner = Coord { col: top_left.col + half * (idx % 2) as isize,
+                                 row: top_left.row + half * (idx / 2) as isize };
+            children[idx] = self.node(level - 1, corner, quarter_cells);
+        }
+
+        self.add(format!("{} {} {} {} {}", level, children[0], children[1], children[2], children[3]))
+
+    }
+
+}
+
+pub fn encode(board: &Board, rule: &Rule, metadata: &Metadata) -> String {
+
+    // cells in states above 1 are saved only for multi-state rules,
+    // their nodes have 2x2 squares of states instead of 8x8 leaves
+    let multistate = rule.get_states() > 2;
+
+    let cells: Vec<(Coord, usize)> = board.iter().
+        filter(|desc| desc.state == 1 || (multistate && desc.state > 1)).
+        map(|CellDesc { coord, state, .. }| (coord, state)).collect();
+
+    // the smallest root centered at the origin which contains all cells
+    let mut level = LEAF_LEVEL;
+
+    while cells.iter().any(|&(c, _)| {
+        let half = 1 << (level - 1);
+        c.col < -half || c.col >= half || c.row < -half || c.row >= half
+    }) {
+        level += 1;
+    }
+
+    let mut encoder = Encoder { lines: Vec::new(), indices: HashMap::new(), multistate };
+
+    let half = 1 << (level - 1);
+    let root = encoder.node(level, Coord { col: -half, row: -half }, cells);
+
+    let mut result = format!("{} (life-rs)\n#R {}\n", HEADER, format_rule(rule, board.get_grid()));
+
+    if let Some(ref name) = metadata.name {
+        result.push_str(&format!("#N {}\n", name));
+    }
+
+    if let Some(ref author) = metadata.author {
+        result.push_str(&format!("#O {}\n", author));
+    }
+
+    for comment in metadata.comments.iter() {
+        result.push_str(&format!("#C {}\n", comment));
+    }
+
+    // an empty board still needs a root node
+    if root == 0 {
+        result.push_str(&format!("{} 0 0 0 0\n", level));
+    }
+
+    for line in encoder.lines {
+        result.push_str(&line);
+        result.push('\n');
+    }
+
+    result
+
+}
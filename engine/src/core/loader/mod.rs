@@ -0,0 +1,1191 @@
+extern crate flate2;
+
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::error;
+use std::fmt;
+use std::fmt::{Formatter, Display};
+
+use self::flate2::read::MultiGzDecoder;
+
+use super::{Coord, Rule};
+use board::{Board, Grid};
+use board::transform::{Transform, bounding_box, transform_region};
+
+mod rle;
+mod plaintext;
+mod life105;
+mod life106;
+mod macrocell;
+
+use self::rle::encode;
+
+// first bytes of gzip-compressed data
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// cells and corners further from the origin are rejected, so sizes
+// and offsets of patterns always fit into isize
+const MAX_COORD: isize = isize::MAX / 4;
+
+// patterns with more cells wouldn't fit into memory
+const MAX_CELLS: usize = 1 << 24;
+
+
+/// Errors of pattern parsing, each of them has the line and the column
+/// where it was found, both start from 1
+#[derive(Debug)]
+pub enum ParseError {
+    NotANumber(String, usize, usize),
+    UnexpectedSymbol(String, usize, usize),
+    InputExhausted(usize, usize),
+    EmptyName(usize, usize),
+    WrongName(String, usize, usize),
+    WrongRule(String, usize, usize),
+    MissingParam(String, usize, usize),
+    // symbol of a cell state
+    UnknownCell(char, usize, usize),
+    // pattern has more than MAX_CELLS cells
+    TooManyCells(usize, usize),
+    // input couldn't be read
+    Io(io::Error, usize, usize),
+}
+
+impl ParseError {
+
+    fn at_line(self, line_no: usize) -> ParseError {
+
+        // helpers parsing a single line don't know its number, they
+        // report line 0 and their callers set the actual one
+
+        match self {
+            ParseError::NotANumber(unparsed, _, col) => ParseError::NotANumber(unparsed, line_no, col),
+            ParseError::UnexpectedSymbol(unparsed, _, col) => ParseError::UnexpectedSymbol(unparsed, line_no, col),
+            ParseError::InputExhausted(_, col) => ParseError::InputExhausted(line_no, col),
+            ParseError::EmptyName(_, col) => ParseError::EmptyName(line_no, col),
+            ParseError::WrongName(unparsed, _, col) => ParseError::WrongName(unparsed, line_no, col),
+            ParseError::WrongRule(unparsed, _, col) => ParseError::WrongRule(unparsed, line_no, col),
+            ParseError::MissingParam(name, _, col) => ParseError::MissingParam(name, line_no, col),
+            ParseError::UnknownCell(c, _, col) => ParseError::UnknownCell(c, line_no, col),
+            ParseError::TooManyCells(_, col) => ParseError::TooManyCells(line_no, col),
+            ParseError::Io(err, _, col) => ParseError::Io(err, line_no, col),
+        }
+
+    }
+
+}
+
+impl Display for ParseError {
+
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+
+        match *self {
+            ParseError::NotANumber(ref unparsed, line, col) =>
+                write!(f, "Not a number {} at line {}, column {}", unparsed, line, col),
+            ParseError::UnexpectedSymbol(ref unparsed, line, col) =>
+                write!(f, "Unexpected symbol {} at line {}, column {}", unparsed, line, col),
+            ParseError::InputExhausted(line, col) =>
+                write!(f, "Input exhausted at line {}, column {}", line, col),
+            ParseError::EmptyName(line, col) =>
+                write!(f, "Option name can't be empty at line {}, column {}", line, col),
+            ParseError::WrongName(ref unparsed, line, col) =>
+                write!(f, "Wrong option name {} at line {}, column {}", unparsed, line, col),
+            ParseError::WrongRule(ref unparsed, line, col) =>
+                write!(f, "Wrong rule {} at line {}, column {}", unparsed, line, col),
+            ParseError::MissingParam(ref name, line, col) =>
+                write!(f, "Missing header parameter {} at line {}, column {}", name, line, col),
+            ParseError::UnknownCell(c, line, col) =>
+                write!(f, "Unknown cell state {} at line {}, column {}", c, line, col),
+            ParseError::TooManyCells(line, col) =>
+                write!(f, "Too many cells at line {}, column {}", line, col),
+            ParseError::Io(ref err, line, col) =>
+                write!(f, "Couldn't read input at line {}, column {}: {}", line, col, err),
+        }
+
+    }
+
+}
+
+impl error::Error for ParseError {
+
+    fn description(&self) -> &str {
+
+        match *self {
+            ParseError::NotANumber(..) => "Not a number",
+            ParseError::UnexpectedSymbol(..) => "Unexpected symbol",
+            ParseError::InputExhausted(..) => "Input exhausted",
+            ParseError::EmptyName(..) => "Option name can't be empty",
+            ParseError::WrongName(..) => "Wrong option name",
+            ParseError::WrongRule(..) => "Wrong rule",
+            ParseError::MissingParam(..) => "Missing header parameter",
+            ParseError::UnknownCell(..) => "Unknown cell state",
+            ParseError::TooManyCells(..) => "Too many cells",
+            ParseError::Io(..) => "Couldn't read input",
+        }
+
+    }
+
+}
+
+
+/// Name, author and comments of a pattern given by `#N`, `#O`
+/// and `#C` lines
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+}
+
+/// Pattern read from a file or a string, `width` and `height` define
+/// its bounding box, `rule` and `grid` are set only if they were given
+/// in the header, `states` has the state of every cell of `coords`,
+/// they are all 1 except for multi-state patterns, `offset` is the
+/// position of the top left corner given by `#P` or `#R` line
+#[derive(Debug)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub rule: Option<Rule>,
+    pub grid: Option<Grid>,
+    pub coords: Vec<Coord>,
+    pub states: Vec<usize>,
+    pub metadata: Metadata,
+    pub offset: Option<Coord>,
+}
+
+impl Metadata {
+
+    fn push_description(&mut self, text: &str) {
+
+        // formats without dedicated name and author lines use `Name:`
+        // and `Author:` prefixes, all the other lines are comments
+
+        let text = text.trim();
+
+        if let Some(name) = text.strip_prefix("Name:") {
+            self.name = Some(name.trim().to_string());
+        } else if let Some(author) = text.strip_prefix("Author:") {
+            self.author = Some(author.trim().to_string());
+        } else {
+            self.comments.push(text.to_string());
+        }
+
+    }
+
+    fn descriptions(&self) -> Vec<String> {
+
+        // lines of metadata with `Name:` and `Author:` prefixes
+
+        let mut result = Vec::new();
+
+        if let Some(ref name) = self.name {
+            result.push(format!("Name: {}", name));
+        }
+
+        if let Some(ref author) = self.author {
+            result.push(format!("Author: {}", author));
+        }
+
+        result.extend(self.comments.iter().cloned());
+        result
+
+    }
+
+}
+
+impl Pattern {
+
+    pub fn transform(&mut self, transform: Transform) {
+
+        // rotate or flip the pattern inside its rectangle, the rectangle
+        // keeps its top left corner, so the offset stays the same
+
+        let (_, bottom_right) = match bounding_box(&self.coords) {
+            Some(bbox) => bbox,
+            None => return,
+        };
+
+        // cells may lie beyond the sizes given in the header
+        let width = self.width.max(bottom_right.col as usize + 1);
+        let height = self.height.max(bottom_right.row as usize + 1);
+
+        let origin = Coord { col: 0, row: 0 };
+        let corner = Coord { col: width as isize - 1, row: height as isize - 1 };
+
+        self.coords = transform_region(&self.coords, transform, origin, corner);
+
+        let (width, height) = if transform.swaps_sides() { (height, width) } else { (width, height) };
+        self.width = width;
+        self.height = height;
+
+    }
+
+    pub fn translate(&mut self, dx: isize, dy: isize) {
+        // move the pattern, cell coordinates stay relative to its top left corner
+        let Coord { col, row } = self.offset.unwrap_or(Coord { col: 0, row: 0 });
+        self.offset = Some(Coord { col: col + dx, row: row + dy });
+    }
+
+    fn from_absolute(coords: Vec<Coord>, states: Vec<usize>, rule: Option<Rule>,
+                     grid: Option<Grid>, metadata: Metadata) -> Pattern {
+
+        // formats which give absolute positions of cells are converted to
+        // coordinates relative to the top left corner of the bounding box,
+        // the corner itself becomes the offset of the pattern
+
+        let top_left = coords.iter().fold(None, |acc: Option<Coord>, c| match acc {
+            Some(tl) => Some(Coord { col: tl.col.min(c.col), row: tl.row.min(c.row) }),
+            None => Some(*c),
+        });
+
+        let origin = top_left.unwrap_or(Coord { col: 0, row: 0 });
+        let coords: Vec<Coord> = coords.into_iter().
+            map(|c| Coord { col: c.col - origin.col, row: c.row - origin.row }).collect();
+
+        Pattern {
+            width: coords.iter().map(|c| c.col as usize + 1).max().unwrap_or(0),
+            height: coords.iter().map(|c| c.row as usize + 1).max().unwrap_or(0),
+            rule,
+            grid,
+            coords,
+            states,
+            metadata,
+            offset: top_left,
+        }
+
+    }
+
+}
+
+fn parse_rule(value: &str, line_no: usize, col_no: usize) -> Result<(Rule, Option<Grid>), ParseError> {
+
+    // rule may be followed by the board topology, e.g. "B3/S23:T30,20",
+    // position of the value is used in error messages
+
+    let mut parts = value.trim().splitn(2, ':');
+
+    let rule = match parts.next().map(Rule::parse) {
+        Some(Ok(parsed)) => parsed,
+        _ => return Err(ParseError::WrongRule(String::from(value), line_no, col_no)),
+    };
+
+    match parts.next().map(Grid::parse) {
+        Some(Ok(parsed)) => Ok((rule, Some(parsed))),
+        Some(Err(_)) => Err(ParseError::WrongRule(String::from(value), line_no, col_no)),
+        None => Ok((rule, None)),
+    }
+
+}
+
+fn column_of(line: &str, part: &str) -> usize {
+
+    // column of a part of a line, the part must be a slice of the line
+
+    let offset = part.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+
+}
+
+fn parse_coords(line: &str, text: &str, line_no: usize) -> Result<Coord, ParseError> {
+
+    // column and row separated by spaces, e.g. of a cell or of the top
+    // left corner of a pattern, the text must be a slice of the line
+
+    let numbers: Vec<&str> = text.split_whitespace().collect();
+
+    if numbers.len() != 2 {
+        return Err(ParseError::NotANumber(String::from(text), line_no, column_of(line, text)));
+    }
+
+    let mut values = [0; 2];
+
+    for (value, number) in values.iter_mut().zip(numbers) {
+        match number.parse::<isize>() {
+            Ok(parsed) if (-MAX_COORD..=MAX_COORD).contains(&parsed) => *value = parsed,
+            Ok(_) => return Err(ParseError::UnexpectedSymbol(String::from(number), line_no, column_of(line, number))),
+            Err(_) => return Err(ParseError::NotANumber(String::from(text), line_no, column_of(line, text))),
+        }
+    }
+
+    Ok(Coord { col: values[0], row: values[1] })
+
+}
+
+fn format_rule(rule: &Rule, grid: Grid) -> String {
+
+    // topology of bounded boards is saved as a suffix of the rule
+
+    if grid.is_bounded() {
+        format!("{}:{}", rule, grid)
+    } else {
+        format!("{}", rule)
+    }
+
+}
+
+trait InputProviderTrait {
+    fn read_line(&mut self) -> Option<String>;
+}
+
+struct ReadInputProvider<R: BufRead> {
+    buf_reader: R,
+    // number of lines read so far and the error which stopped reading,
+    // parsers count lines the same way, line numbers are used in error
+    // messages of both of them
+    line_no: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReadInputProvider<R> {
+
+    pub fn new(buf_reader: R) -> Self {
+        Self { buf_reader, line_no: 0, error: None }
+    }
+
+}
+
+struct IterHelper<'a, T: 'a> {
+    obj: &'a mut T,
+}
+
+impl<'a, T: 'a> Iterator for IterHelper<'a, T>
+    where T: InputProviderTrait {
+
+    type Item=String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.obj.read_line()
+    }
+
+}
+
+impl<'a, R: BufRead> IntoIterator for &'a mut ReadInputProvider<R> {
+
+    type Item = String;
+    type IntoIter = IterHelper<'a, ReadInputProvider<R>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IterHelper{obj: self}
+    }
+
+}
+
+impl<R: BufRead> InputProviderTrait for ReadInputProvider<R> {
+
+    fn read_line(&mut self) -> Option<String> {
+
+        // reading errors end the input, they are reported once parsing is done
+
+        if self.error.is_some() {
+            return None;
+        }
+
+        let mut line = String::new();
+
+        match self.buf_reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                self.line_no += 1;
+
+                // line terminators are not passed to parsers
+                let len = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(len);
+
+                Some(line)
+            },
+            Err(err) => {
+                self.error = Some(err);
+                None
+            },
+        }
+
+    }
+
+}
+
+/// Supported pattern file formats, Life 1.05 and Life 1.06 files
+/// share `.lif` extension, it's saved in Life 1.06 format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+
+    pub fn from_extension(file_name: &str) -> Option<Format> {
+        let file_name = file_name.to_lowercase();
+
+        if file_name.ends_with(".rle") {
+            Some(Format::Rle)
+        } else if file_name.ends_with(".cells") {
+            Some(Format::Plaintext)
+        } else if file_name.ends_with(".lif") || file_name.ends_with(".life") {
+            Some(Format::Life106)
+        } else if file_name.ends_with(".mc") {
+            Some(Format::Macrocell)
+        } else {
+            None
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Format> {
+
+        // Life and Macrocell files start with their headers, empty lines
+        // and other `#` comments don't tell the format, plaintext patterns
+        // start with `!` comments or rows of cells, headerless Life 1.06
+        // files with coordinates, anything else has to be an RLE header
+
+        let line = line.trim();
+
+        if line.starts_with(life105::HEADER) {
+            Some(Format::Life105)
+        } else if line.starts_with(life106::HEADER) {
+            Some(Format::Life106)
+        } else if line.starts_with(macrocell::HEADER) {
+            Some(Format::Macrocell)
+        } else if line.is_empty() || line.starts_with('#') {
+            None
+        } else if line.starts_with('!') || line.chars().all(|c| c == '.' || c == 'O' || c == '*') {
+            Some(Format::Plaintext)
+        } else if line.split_whitespace().count() == 2 && line.split_whitespace().all(|x| x.parse::<isize>().is_ok()) {
+            Some(Format::Life106)
+        } else {
+            Some(Format::Rle)
+        }
+
+    }
+
+}
+
+fn parse_stream<T>(mut lines: T, fallback: Option<Format>) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    // format is detected by the first lines of the input, the fallback,
+    // if any, is used when they don't tell it, lines read to detect the
+    // format are handed to the parser again
+
+    let mut head = Vec::new();
+    let mut format = None;
+
+    for line in &mut lines {
+        format = Format::from_line(&line);
+        head.push(line);
+        if format.is_some() {
+            break;
+        }
+    }
+
+    let lines = head.into_iter().chain(lines);
+
+    match format.or(fallback).unwrap_or(Format::Rle) {
+        Format::Rle => rle::parse_stream(lines),
+        Format::Plaintext => plaintext::parse_stream(lines),
+        Format::Life105 => life105::parse_stream(lines),
+        Format::Life106 => life106::parse_stream(lines),
+        Format::Macrocell => macrocell::parse_stream(lines),
+    }
+
+}
+
+fn parse_lines<R: BufRead>(buf_reader: R, fallback: Option<Format>) -> Result<Pattern, ParseError> {
+
+    let mut data_provider = ReadInputProvider::new(buf_reader);
+    let cells_data = parse_stream((&mut data_provider).into_iter(), fallback);
+
+    // input ended by an error can't be trusted, so the error is reported
+    // instead of the result of parsing
+    match data_provider.error {
+        Some(err) => Err(ParseError::Io(err, data_provider.line_no + 1, 1)),
+        None => cells_data,
+    }
+
+}
+
+fn parse_reader<R: Read>(reader: R, fallback: Option<Format>) -> Result<Pattern, ParseError> {
+
+    // gzip-compressed input is recognized by its first bytes
+    // and decompressed on the fly
+
+    let mut buf_reader = BufReader::new(reader);
+
+    let compressed = match buf_reader.fill_buf() {
+        Ok(buf) => buf.starts_with(&GZIP_MAGIC),
+        Err(err) => return Err(ParseError::Io(err, 1, 1)),
+    };
+
+    if compressed {
+        parse_lines(BufReader::new(MultiGzDecoder::new(buf_reader)), fallback)
+    } else {
+        parse_lines(buf_reader, fallback)
+    }
+
+}
+
+pub fn from_reader<R: Read>(reader: R) -> Result<Pattern, ParseError> {
+
+    // read a pattern in any supported format from a file, stdin,
+    // a memory buffer or any other stream, it may be gzip-compressed
+
+    parse_reader(reader, None)
+
+}
+
+pub fn from_file(file_name: String) -> Result<Pattern, ParseError> {
+
+    // accepted file formats described here:
+    // http://www.conwaylife.com/w/index.php?title=Run_Length_Encoded
+    // http://www.conwaylife.com/wiki/Plaintext
+    // http://www.conwaylife.com/wiki/Life_1.05
+    // http://www.conwaylife.com/wiki/Life_1.06
+    // http://golly.sourceforge.net/Help/formats.html#mc
+    // format is detected by the content of a file or by its extension,
+    // files may be gzip-compressed, e.g. `pattern.rle.gz`
+
+    let format = Format::from_extension(file_name.trim_end_matches(".gz"));
+
+    match File::open(file_name) {
+        Ok(f) => parse_reader(f, format),
+        Err(err) => Err(ParseError::Io(err, 1, 1)),
+    }
+
+}
+
+pub fn from_string(input_string: String) -> Result<Pattern, ParseError> {
+    from_reader(input_string.as_bytes())
+}
+
+
+pub fn to_string(board: &Board, rule: &Rule, metadata: &Metadata) -> String {
+
+    // encode all live cells of a board in RLE format, cells in other
+    // states are encoded for multi-state rules, position of the pattern
+    // is saved unless it starts at the origin
+
+    let origin = Coord { col: 0, row: 0 };
+
+    match board.get_bounding_box() {
+        Some((top_left, bottom_right)) => encode(board, rule, metadata, Some(top_left).filter(|&x| x != origin),
+                                                 top_left, bottom_right),
+        None => encode(board, rule, metadata, None, origin, Coord { col: -1, row: -1 }),
+    }
+
+}
+
+pub fn region_to_string(board: &Board, rule: &Rule, top_left: Coord, bottom_right: Coord) -> String {
+
+    // encode live cells of a rectangular region of a board in RLE format,
+    // both corners are included into the region
+
+    encode(board, rule, &Metadata::default(), None, top_left, bottom_right)
+
+}
+
+pub fn to_plaintext(board: &Board, metadata: &Metadata) -> String {
+
+    // encode all live cells of a board in plaintext format, neither
+    // the rule nor the position of the pattern can be saved
+
+    match board.get_bounding_box() {
+        Some((top_left, bottom_right)) => plaintext::encode(board, metadata, top_left, bottom_right),
+        None => plaintext::encode(board, metadata, Coord { col: 0, row: 0 }, Coord { col: -1, row: -1 }),
+    }
+
+}
+
+pub fn to_format(board: &Board, rule: &Rule, metadata: &Metadata, format: Format) -> String {
+
+    // encode a board in the given format, formats other than RLE and
+    // Macrocell keep only live cells
+
+    match format {
+        Format::Rle => to_string(board, rule, metadata),
+        Format::Plaintext => to_plaintext(board, metadata),
+        Format::Life105 => life105::encode(board, rule, metadata),
+        Format::Life106 => life106::encode(board),
+        Format::Macrocell => macrocell::encode(board, rule, metadata),
+    }
+
+}
+
+pub fn to_file(board: &Board, rule: &Rule, metadata: &Metadata, file_name: String) -> Result<(), io::Error> {
+
+    // format is chosen by the extension of a file, RLE is used by default
+
+    let format = Format::from_extension(&file_name).unwrap_or(Format::Rle);
+    let encoded = to_format(board, rule, metadata, format);
+
+    let mut f = File::create(file_name)?;
+    f.write_all(encoded.as_bytes())
+
+}
+
+#[test]
+fn test_parse_rle1() {
+    // glyder
+    assert!(from_string(String::from("x =  3, y = 0\nbo$2b\no$3o!")).unwrap().coords ==
+    [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+     Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }]);
+}
+
+#[test]
+fn test_parse_rle2() {
+    // Gosper glider gun
+    assert!(from_string(String::from("x= 20, y=100\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2\
+    o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!")).unwrap().coords ==
+           [Coord { col: 24, row: 0 }, Coord { col: 22, row: 1 }, Coord { col: 24, row: 1 },
+             Coord { col: 12, row: 2 }, Coord { col: 13, row: 2 }, Coord { col: 20, row: 2 },
+             Coord { col: 21, row: 2 }, Coord { col: 34, row: 2 }, Coord { col: 35, row: 2 },
+             Coord { col: 11, row: 3 }, Coord { col: 15, row: 3 }, Coord { col: 20, row: 3 },
+             Coord { col: 21, row: 3 }, Coord { col: 34, row: 3 }, Coord { col: 35, row: 3 },
+             Coord { col: 0, row: 4 }, Coord { col: 1, row: 4 }, Coord { col: 10, row: 4 },
+             Coord { col: 16, row: 4 }, Coord { col: 20, row: 4 }, Coord { col: 21, row: 4 },
+             Coord { col: 0, row: 5 }, Coord { col: 1, row: 5 }, Coord { col: 10, row: 5 },
+             Coord { col: 14, row: 5 }, Coord { col: 16, row: 5 }, Coord { col: 17, row: 5 },
+             Coord { col: 22, row: 5 }, Coord { col: 24, row: 5 }, Coord { col: 10, row: 6 },
+             Coord { col: 16, row: 6 }, Coord { col: 24, row: 6 }, Coord { col: 11, row: 7 },
+             Coord { col: 15, row: 7 }, Coord { col: 12, row: 8 }, Coord { col: 13, row: 8 }][..]);
+}
+
+#[test]
+fn test_parse_rle_row_skips() {
+    // two blinkers with two empty rows between them
+    assert_eq!(from_string(String::from("x = 3, y = 4\n3o3$3o!")).unwrap().coords,
+               [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 2, row: 0 },
+                Coord { col: 0, row: 3 }, Coord { col: 1, row: 3 }, Coord { col: 2, row: 3 }]);
+
+    // leading empty rows and a run count before the terminator
+    assert_eq!(from_string(String::from("x = 1, y = 3\n2$o3!")).unwrap().coords, [Coord { col: 0, row: 2 }]);
+}
+
+#[test]
+fn test_parse_rle_layout() {
+    // run count split by a line break, comments between lines of
+    // data and text after the terminator
+    let pattern = from_string(String::from("#N layout\nx = 13, y = 2\n1\n2bo$\n#C comment\n\
+                                            4o! ignored $ 3o\n3o!")).unwrap();
+
+    assert_eq!(pattern.coords, [Coord { col: 12, row: 0 }, Coord { col: 0, row: 1 }, Coord { col: 1, row: 1 },
+                                Coord { col: 2, row: 1 }, Coord { col: 3, row: 1 }]);
+
+    // terminator is optional at the end of input
+    assert_eq!(from_string(String::from("x = 3, y = 1\n3o")).unwrap().coords.len(), 3);
+}
+
+#[test]
+fn test_parse_known_patterns() {
+    // copperhead, a c/10 orthogonal spaceship, has two multi-row skips
+    let copperhead = from_string(String::from("#N Copperhead\nx = 8, y = 12, rule = B3/S23\n\
+        b2o2b2o$3b2o$3b2o$obo2bobo$o6bo2$o6bo$b2o2b2o$2b4o2$3b2o$3b2o!")).unwrap();
+
+    assert_eq!(copperhead.coords.len(), 28);
+    assert_eq!(copperhead.coords.iter().map(|c| c.row).max(), Some(11));
+
+    let mut engine = super::Engine::new(Some(copperhead.coords.clone()), None, None, Rule::default());
+    engine.iterations(10);
+
+    // the same shape moved by one row
+    let (top_left, _) = engine.get_board().get_bounding_box().unwrap();
+    assert_eq!(top_left.row.abs(), 1);
+
+    let mut expected: Vec<Coord> = copperhead.coords.iter().
+        map(|c| Coord { col: c.col, row: c.row + top_left.row }).collect();
+    let mut actual: Vec<Coord> = engine.get_board().get_cells().into_iter().map(|(coord, _)| coord).collect();
+
+    expected.sort();
+    actual.sort();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_parse_metadata() {
+    let pattern = from_string(String::from("#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\n\
+                                            #C It moves diagonally.\n#R -5 7\nx = 3, y = 3\nbo$2bo$3o!")).unwrap();
+
+    assert_eq!(pattern.metadata, Metadata {
+        name: Some(String::from("Glider")),
+        author: Some(String::from("Richard K. Guy")),
+        comments: vec![String::from("The smallest spaceship."), String::from("It moves diagonally.")],
+    });
+    assert_eq!(pattern.offset, Some(Coord { col: -5, row: 7 }));
+
+    // pattern is placed at the offset
+    let mut engine = super::Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&pattern);
+
+    assert_eq!(engine.get_board().get_bounding_box(),
+               Some((Coord { col: -5, row: 7 }, Coord { col: -3, row: 9 })));
+
+    // metadata and position are written back
+    assert_eq!(to_string(engine.get_board(), engine.get_rule(), engine.get_metadata()),
+               "#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\n#C It moves diagonally.\n\
+                #R -5 7\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+
+    // #P is the same as #R, comments may follow the header
+    let pattern = from_string(String::from("x = 3, y = 3\n#P 2 3\n#C late comment\nbo$2bo$3o!")).unwrap();
+    assert_eq!(pattern.offset, Some(Coord { col: 2, row: 3 }));
+    assert_eq!(pattern.metadata.comments, vec![String::from("late comment")]);
+
+    assert!(from_string(String::from("#R 2\nx = 3, y = 3\nbo$2bo$3o!")).is_err());
+}
+
+#[test]
+fn test_parse_header() {
+    let pattern = from_string(String::from("#C glider\nx = 3, y = 3, rule = B36/S23\nbo$2bo$3o!")).unwrap();
+
+    assert_eq!(pattern.width, 3);
+    assert_eq!(pattern.height, 3);
+    assert_eq!(pattern.rule, Some(Rule::new(&[3, 6], &[2, 3])));
+    assert_eq!(pattern.coords.len(), 5);
+
+    // rule is optional
+    assert_eq!(from_string(String::from("x = 3, y = 3\nbo$2bo$3o!")).unwrap().rule, None);
+}
+
+#[test]
+fn test_rule_topology() {
+    let pattern = from_string(String::from("x = 3, y = 3, rule = B3/S23:K30*,20\nbo$2bo$3o!")).unwrap();
+
+    assert_eq!(pattern.rule, Some(Rule::default()));
+    assert_eq!(pattern.grid, Some(Grid::parse("K30*,20").unwrap()));
+
+    assert!(from_string(String::from("x = 3, y = 3, rule = B3/S23:Q30,20\nbo$2bo$3o!")).is_err());
+
+    // topology is written back for bounded boards
+    let mut engine = super::Engine::new(Some(pattern.coords), None, None, Rule::default());
+    engine.set_grid(Grid::parse("T30+2,20").unwrap());
+
+    assert_eq!(to_string(engine.get_board(), &Rule::default(), &Metadata::default()),
+               "x = 3, y = 3, rule = B3/S23:T30+2,20\nbo$2bo$3o!\n");
+}
+
+#[test]
+fn test_parse_header_errors() {
+    assert!(from_string(String::from("x = 3, y = 3, rule = B9/S23\nbo$2bo$3o!")).is_err());
+    assert!(from_string(String::from("x = 3\nbo$2bo$3o!")).is_err());
+    assert!(from_string(String::from("x = 3, y = 3, z = 4\nbo$2bo$3o!")).is_err());
+}
+
+#[test]
+fn test_parse_multistate() {
+    let pattern = from_string(String::from("x = 4, y = 2, rule = WireWorld\n.A2C$B.C!")).unwrap();
+
+    assert_eq!(pattern.rule, Some(Rule::parse("WireWorld").unwrap()));
+    assert_eq!(pattern.coords, vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 0 }, Coord { col: 3, row: 0 },
+                                    Coord { col: 0, row: 1 }, Coord { col: 2, row: 1 }]);
+    assert_eq!(pattern.states, vec![1, 3, 3, 2, 3]);
+
+    // two state patterns have only live cells
+    assert!(from_string(String::from("x = 3, y = 3\nbo$2bo$3o!")).unwrap().states.iter().all(|&x| x == 1));
+
+    // states above 24 have a prefix
+    let pattern = from_string(String::from("x = 3, y = 1\npA2yO!")).unwrap();
+    assert_eq!(pattern.states, vec![25, 255, 255]);
+
+    // unknown symbols are reported with their positions
+    match from_string(String::from("x = 3, y = 2\nbo$\n2bz!")) {
+        Err(ParseError::UnknownCell('z', 3, 3)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    assert!(from_string(String::from("x = 3, y = 1\nyP!")).is_err());
+    assert!(from_string(String::from("x = 3, y = 1\n2p!")).is_err());
+}
+
+#[test]
+fn test_write_multistate() {
+    let pattern = from_string(String::from("x = 6, y = 2, rule = WireWorld\nBA4C$.3C!")).unwrap();
+
+    let mut engine = super::Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&pattern);
+
+    let encoded = to_string(engine.get_board(), engine.get_rule(), &Metadata::default());
+    assert_eq!(encoded, "x = 6, y = 2, rule = WireWorld\nBA4C$.3C!\n");
+
+    // dying cells of Generations rules are saved as well
+    let mut engine = super::Engine::new(None, None, None, Rule::parse("B2/S/C3").unwrap());
+    engine.load_pattern(&from_string(String::from("x = 3, y = 1, rule = B2/S/C3\nA.B!")).unwrap());
+
+    assert_eq!(to_string(engine.get_board(), engine.get_rule(), &Metadata::default()), "x = 3, y = 1, rule = B2/S/C3\nA.B!\n");
+}
+
+#[test]
+fn test_write_rle() {
+    let glider = from_string(String::from("x = 3, y = 3\nbo$2bo$3o!")).unwrap().coords;
+    let engine = super::Engine::new(Some(glider), None, None, Rule::default());
+
+    assert_eq!(to_string(engine.get_board(), &Rule::default(), &Metadata::default()), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+
+    // region is cropped and empty rows are merged into a single run
+    let pattern = from_string(String::from("x = 5, y = 5\n3o$$$$2b3o!")).unwrap().coords;
+    let engine = super::Engine::new(Some(pattern), None, None, Rule::default());
+
+    assert_eq!(region_to_string(engine.get_board(), &Rule::default(),
+                                Coord { col: 1, row: 0 }, Coord { col: 3, row: 4 }),
+               "x = 3, y = 5, rule = B3/S23\n2o4$b2o!\n");
+}
+
+#[test]
+fn test_write_rle_roundtrip() {
+    // every other cell is alive so the lines have to be wrapped
+    let coords: Vec<Coord> = (0..100).map(|col| Coord { col: 2 * col, row: col % 3 }).collect();
+    let engine = super::Engine::new(Some(coords.clone()), None, None, Rule::default());
+
+    let encoded = to_string(engine.get_board(), &Rule::default(), &Metadata::default());
+
+    assert!(encoded.lines().all(|line| line.len() <= 70));
+
+    let mut decoded = from_string(encoded).unwrap().coords;
+    let mut expected = coords;
+
+    decoded.sort();
+    expected.sort();
+
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn test_parse_plaintext() {
+    let pattern = from_string(String::from("!Name: Glider\n!Author: Richard K. Guy\n!The smallest spaceship.\n\
+                                            .O\n..O\nOOO\n")).unwrap();
+
+    assert_eq!(pattern.coords, [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                                Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }]);
+    assert_eq!((pattern.width, pattern.height), (3, 3));
+    assert_eq!(pattern.rule, None);
+    assert_eq!(pattern.metadata, Metadata {
+        name: Some(String::from("Glider")),
+        author: Some(String::from("Richard K. Guy")),
+        comments: vec![String::from("The smallest spaceship.")],
+    });
+
+    // without comments the format is detected by cells, `*` is accepted
+    // for live cells and empty lines are empty rows
+    let pattern = from_string(String::from("**\n\n.*")).unwrap();
+    assert_eq!(pattern.coords, [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 1, row: 2 }]);
+    assert_eq!(pattern.height, 3);
+
+    match from_string(String::from("!Name: broken\n.O\n.o")) {
+        Err(ParseError::UnknownCell('o', 3, 2)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // RLE is still detected after comments
+    assert_eq!(from_string(String::from("#C glider\n\nx = 3, y = 3\nbo$2bo$3o!")).unwrap().coords.len(), 5);
+}
+
+#[test]
+fn test_write_plaintext() {
+    let pattern = from_string(String::from("#N Blinker and block\nx = 6, y = 4\n3o$$4b2o$4b2o!")).unwrap();
+
+    let mut engine = super::Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&pattern);
+
+    let encoded = to_plaintext(engine.get_board(), engine.get_metadata());
+    assert_eq!(encoded, "!Name: Blinker and block\nOOO\n.\n....OO\n....OO\n");
+
+    let decoded = from_string(encoded).unwrap();
+
+    assert_eq!(decoded.coords, pattern.coords);
+    assert_eq!(decoded.metadata, pattern.metadata);
+}
+
+#[test]
+fn test_parse_life() {
+    let glider = [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                  Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }];
+
+    // absolute positions of cells become the offset of a pattern
+    let pattern = from_string(String::from("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n")).unwrap();
+    assert_eq!(pattern.coords, glider);
+    assert_eq!(pattern.offset, Some(Coord { col: -1, row: -1 }));
+    assert_eq!((pattern.width, pattern.height), (3, 3));
+
+    // header is optional
+    assert_eq!(from_string(String::from("0 -1\n1 0\n-1 1\n0 1\n1 1")).unwrap().coords, glider);
+    assert!(from_string(String::from("#Life 1.06\n0 -1\n1 x\n")).is_err());
+
+    let pattern = from_string(String::from("#Life 1.05\n#D Name: Glider\n#D The smallest spaceship.\n\
+                                            #R 23/36\n#P -1 -1\n.*\n..*\n***\n")).unwrap();
+    assert_eq!(pattern.coords, glider);
+    assert_eq!(pattern.offset, Some(Coord { col: -1, row: -1 }));
+    assert_eq!(pattern.rule, Some(Rule::new(&[3, 6], &[2, 3])));
+    assert_eq!(pattern.metadata.name, Some(String::from("Glider")));
+    assert_eq!(pattern.metadata.comments, vec![String::from("The smallest spaceship.")]);
+
+    // blocks are placed at their own positions
+    let pattern = from_string(String::from("#Life 1.05\n#N\n#P 0 0\n**\n#P 10 5\n*\n")).unwrap();
+    assert_eq!(pattern.rule, Some(Rule::default()));
+    assert_eq!(pattern.coords, [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 10, row: 5 }]);
+
+    match from_string(String::from("#Life 1.05\n#P 0 0\n.*\n.x")) {
+        Err(ParseError::UnknownCell('x', 4, 2)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_macrocell() {
+    // glider in the south east quarter of a 16x16 root
+    let pattern = from_string(String::from("[M2] (golly 2.0)\n#R B3/S23\n#G 0\n$$..*$...*$.***$\n4 0 0 0 1\n")).unwrap();
+
+    assert_eq!(pattern.rule, Some(Rule::default()));
+    assert_eq!(pattern.offset, Some(Coord { col: 1, row: 2 }));
+    assert_eq!(pattern.coords, [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                                Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }]);
+
+    // identical quarters share a node
+    let pattern = from_string(String::from("[M2]\n**$\n4 1 1 1 1\n")).unwrap();
+    assert_eq!(pattern.coords.len(), 8);
+    assert_eq!(pattern.offset, Some(Coord { col: -8, row: -8 }));
+
+    // multi-state nodes of 2x2 squares
+    let pattern = from_string(String::from("[M2]\n#R WireWorld\n1 0 1 2 3\n2 1 0 0 1\n")).unwrap();
+    assert_eq!(pattern.states, vec![1, 2, 3, 1, 2, 3]);
+
+    // nodes may refer only to nodes of the previous level read before them
+    assert!(from_string(String::from("[M2]\n4 0 0 0 1\n")).is_err());
+    assert!(from_string(String::from("[M2]\n*$\n5 0 0 0 1\n")).is_err());
+    assert!(from_string(String::from("[M2]\n*********$\n")).is_err());
+
+    // a chain of empty nodes is not expanded
+    let chain: String = (3..63).map(|level| format!("{} {} {} {} {}\n", level, level - 2, level - 2, level - 2, level - 2)).collect();
+    let pattern = from_string(format!("[M2]\n2 0 0 0 0\n{}", chain)).unwrap();
+    assert!(pattern.coords.is_empty());
+
+    // 64 cells of a leaf repeated 4^10 times
+    let leaf = "********$".repeat(8);
+    let nodes: String = (4..14).map(|level| format!("{} {} {} {} {}\n", level, level - 3, level - 3, level - 3, level - 3)).collect();
+    match from_string(format!("[M2]\n{}\n{}", leaf, nodes)) {
+        Err(ParseError::TooManyCells(12, 1)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_write_formats() {
+    // glider at a negative position and a block far away
+    let pattern = from_string(String::from("#N Glider and block\n#R -3 -2\nx = 3, y = 3\nbo$2bo$3o!")).unwrap();
+
+    let mut engine = super::Engine::new(None, None, None, Rule::parse("B36/S23").unwrap());
+    engine.load_pattern(&pattern);
+
+    for &(col, row) in [(200, 100), (201, 100), (200, 101), (201, 101)].iter() {
+        engine.get_board_mut().born_at(col, row);
+    }
+
+    let mut expected: Vec<Coord> = engine.get_board().get_cells().into_iter().map(|(coord, _)| coord).collect();
+    expected.sort();
+
+    for &format in [Format::Rle, Format::Life105, Format::Life106, Format::Macrocell].iter() {
+        let encoded = to_format(engine.get_board(), engine.get_rule(), engine.get_metadata(), format);
+        let decoded = from_string(encoded).unwrap();
+
+        let mut restored = super::Engine::new(None, None, None, Rule::default());
+        restored.load_pattern(&decoded);
+
+        let mut actual: Vec<Coord> = restored.get_board().get_cells().into_iter().map(|(coord, _)| coord).collect();
+        actual.sort();
+
+        assert_eq!(actual, expected, "{:?}", format);
+
+        if format != Format::Life106 {
+            assert_eq!(restored.get_rule(), engine.get_rule(), "{:?}", format);
+            assert_eq!(restored.get_metadata().name, Some(String::from("Glider and block")), "{:?}", format);
+        }
+    }
+
+    let encoded = to_format(engine.get_board(), engine.get_rule(), &Metadata::default(), Format::Life105);
+    assert!(encoded.lines().all(|line| line.len() <= 80));
+    assert!(encoded.starts_with("#Life 1.05\n#R B36/S23\n#P -80 -2\n"));
+
+    // multi-state patterns
+    let mut engine = super::Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&from_string(String::from("x = 6, y = 2, rule = WireWorld\nBA4C$.3C!")).unwrap());
+
+    let decoded = from_string(to_format(engine.get_board(), engine.get_rule(),
+                                        &Metadata::default(), Format::Macrocell)).unwrap();
+
+    let mut restored = super::Engine::new(None, None, None, Rule::default());
+    restored.load_pattern(&decoded);
+
+    assert_eq!(to_string(restored.get_board(), restored.get_rule(), &Metadata::default()),
+               "x = 6, y = 2, rule = WireWorld\nBA4C$.3C!\n");
+
+    // empty board
+    let engine = super::Engine::new(None, None, None, Rule::default());
+    let decoded = from_string(to_format(engine.get_board(), engine.get_rule(),
+                                        &Metadata::default(), Format::Macrocell)).unwrap();
+    assert!(decoded.coords.is_empty());
+}
+
+#[test]
+fn test_parse_error_positions() {
+    let position = |input: &str| match from_string(String::from(input)) {
+        Err(ParseError::NotANumber(_, line, col)) | Err(ParseError::UnexpectedSymbol(_, line, col)) |
+        Err(ParseError::InputExhausted(line, col)) | Err(ParseError::EmptyName(line, col)) |
+        Err(ParseError::WrongName(_, line, col)) | Err(ParseError::WrongRule(_, line, col)) |
+        Err(ParseError::MissingParam(_, line, col)) | Err(ParseError::UnknownCell(_, line, col)) |
+        Err(ParseError::Io(_, line, col)) | Err(ParseError::TooManyCells(line, col)) => (line, col),
+        other => panic!("unexpected result {:?}", other),
+    };
+
+    // RLE header
+    assert_eq!(position("#C glider\nx = a3, y = 3\nbo$2bo$3o!"), (2, 5));
+    assert_eq!(position("x = 3, y =\nbo$2bo$3o!"), (1, 11));
+    assert_eq!(position("x = 3, = 3\nbo$2bo$3o!"), (1, 8));
+    assert_eq!(position("x = 3, y2 = 3\nbo$2bo$3o!"), (1, 8));
+    assert_eq!(position("x = 3, y = 3, rule = B9/S23\nbo$2bo$3o!"), (1, 22));
+    assert_eq!(position("x = 3, y = 3, z = 3\nbo$2bo$3o!"), (1, 15));
+    assert_eq!(position("x = 3\nbo$2bo$3o!"), (1, 1));
+    assert_eq!(position("#R 1 a\nx = 3, y = 3\nbo$2bo$3o!"), (1, 4));
+    assert_eq!(position("#C only comments\n"), (2, 1));
+
+    // other formats
+    assert_eq!(position("#Life 1.06\n0 0\n  1 x\n"), (3, 3));
+    assert_eq!(position("#Life 1.05\n#R 23/9\n*\n"), (2, 4));
+    assert_eq!(position("[M2]\n**$\n4 1 0 x 1\n"), (3, 7));
+    assert_eq!(position("[M2]\n**$\n4 1 0 2 1\n"), (3, 7));
+    assert_eq!(position("[M2]\n.........*$\n"), (2, 9));
+    assert_eq!(position("[M2]\n1 0 99999 0 0\n"), (2, 5));
+    assert_eq!(position("[M2]\n#R WireWorld\n1 0 1 4 0\n"), (3, 7));
+}
+
+#[test]
+fn test_parse_overflows() {
+    let error = |input: &str| match from_string(String::from(input)) {
+        Err(err) => err,
+        Ok(_) => panic!("{:?} is parsed", input),
+    };
+
+    // numbers which don't fit into isize
+    match error("x = 99999999999999999999, y = 1\no!") {
+        ParseError::NotANumber(ref number, 1, 5) if number == "99999999999999999999" => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    match error("x = 1, y = 1\n99999999999999999999o!") {
+        ParseError::UnexpectedSymbol(_, 2, 19) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    match error("x = 1, y = 1\n2305843009213693951$$o!") {
+        ParseError::UnexpectedSymbol(_, 2, 21) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    // cells and corners too far from the origin
+    match error("#Life 1.05\n#P 9223372036854775807 0\n**") {
+        ParseError::UnexpectedSymbol(_, 2, 4) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    match error("#Life 1.06\n-9223372036854775807 0\n9223372036854775807 0\n") {
+        ParseError::UnexpectedSymbol(_, 2, 1) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    match error("#P 0 -9223372036854775808\nx = 1, y = 1\no!") {
+        ParseError::UnexpectedSymbol(_, 1, 6) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    // runs may not move cells too far either
+    match error("x = 1, y = 1\n2305843009213693951bo$o!") {
+        ParseError::UnexpectedSymbol(_, 2, 21) => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    // runs of live cells are limited by the number of cells, not by coordinates
+    match error("x = 1, y = 1\nbo3000000000000o!") {
+        ParseError::UnexpectedSymbol(ref run, 2, 3) if run == "3000000000000" => {},
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    assert_eq!(from_string(String::from("x = 1, y = 1\n3000000000000bo!")).unwrap().coords.len(), 1);
+}
+
+#[test]
+fn test_parse_io_errors() {
+    // reader which fails after the first line
+    struct Broken(bool);
+
+    impl Read for Broken {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 {
+                return Err(io::Error::other("broken"));
+            }
+            self.0 = true;
+            let line = b"x = 3, y = 3\n";
+            buf[..line.len()].copy_from_slice(line);
+            Ok(line.len())
+        }
+    }
+
+    match from_reader(Broken(false)) {
+        Err(ParseError::Io(_, 2, 1)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // invalid UTF-8 is an input error as well
+    match from_reader(&b"x = 3, y = 3\nbo$2bo$\xff3o!"[..]) {
+        Err(ParseError::Io(_, 2, 1)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    match from_file(String::from("no/such/pattern.rle")) {
+        Err(ParseError::Io(..)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // any reader can be used
+    assert_eq!(from_reader(&b"x = 3, y = 3\r\nbo$2bo$3o!\r\n"[..]).unwrap().coords.len(), 5);
+}
+
+#[test]
+fn test_parse_gzip() {
+    use self::flate2::Compression;
+    use self::flate2::write::GzEncoder;
+
+    let compress = |input: &str| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(input.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    };
+
+    // every format is decompressed transparently
+    for input in ["x = 3, y = 3\nbo$2bo$3o!", "!Name: Glider\n.O\n..O\nOOO", "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2",
+                  "[M2]\n$$..*$...*$.***$\n4 0 0 0 1\n"].iter() {
+        let pattern = from_reader(&compress(input)[..]).unwrap();
+        assert_eq!(pattern.coords, [Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                                    Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }]);
+    }
+
+    // corrupted data is an input error
+    let mut corrupted = compress("x = 3, y = 3\nbo$2bo$3o!");
+    let len = corrupted.len();
+    corrupted.truncate(len / 2);
+
+    match from_reader(&corrupted[..]) {
+        Err(ParseError::Io(..)) => {},
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // extension under .gz is used when content doesn't tell the format,
+    // comments alone are an empty Life 1.06 pattern but not an RLE one
+    let empty = compress("#D empty\n");
+    assert!(from_reader(&empty[..]).is_err());
+
+    let file_name = ::std::env::temp_dir().join("life_rs_test_parse_gzip.lif.gz");
+    File::create(&file_name).unwrap().write_all(&empty).unwrap();
+
+    let pattern = from_file(file_name.to_string_lossy().into_owned());
+    ::std::fs::remove_file(&file_name).unwrap();
+
+    assert!(pattern.unwrap().coords.is_empty());
+}
@@ -0,0 +1,107 @@
+use super::{Metadata, ParseError, Pattern};
+use super::super::Coord;
+use board::{Board, CellDesc};
+
+
+pub fn parse_stream<T>(mut lines: T) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    // accepted file format described here:
+    // http://www.conwaylife.com/wiki/Plaintext
+
+    let mut metadata = Metadata::default();
+
+    let mut coords = Vec::new();
+    let mut width = 0;
+    let mut height = 0;
+
+    let mut line_no = 0;
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        // `!Name:` and `!Author:` lines give name and author of a pattern,
+        // all the other lines starting with `!` are comments
+        if let Some(text) = line.strip_prefix('!') {
+            metadata.push_description(text);
+            continue;
+        }
+
+        // every other line is a row of cells, trailing dead cells may be omitted
+        let line = line.trim_end();
+
+        for (idx, c) in line.chars().enumerate() {
+            match c {
+                '.' => {},
+                'O' | '*' => coords.push(Coord { col: idx as isize, row: height as isize }),
+                _ => return Err(ParseError::UnknownCell(c, line_no, idx + 1)),
+            }
+        }
+
+        width = width.max(line.chars().count());
+        height += 1;
+
+    }
+
+    Ok(Pattern {
+        width,
+        height,
+        rule: None,
+        grid: None,
+        states: vec![1; coords.len()],
+        coords,
+        metadata,
+        offset: None,
+    })
+
+}
+
+pub fn encode(board: &Board, metadata: &Metadata, top_left: Coord, bottom_right: Coord) -> String {
+
+    // the format has no header, so only live cells of a region are saved,
+    // each row is written up to its last live cell, a single dot is used
+    // for empty rows
+
+    let mut result = String::new();
+
+    for line in metadata.descriptions() {
+        result.push_str(&format!("!{}\n", line));
+    }
+
+    if top_left.col > bottom_right.col || top_left.row > bottom_right.row {
+        return result;
+    }
+
+    let height = (bottom_right.row - top_left.row + 1) as usize;
+    let mut rows: Vec<Vec<isize>> = vec![Vec::new(); height];
+
+    for CellDesc { coord, state, .. } in board.iter() {
+        if state == 1 &&
+            coord.col >= top_left.col && coord.col <= bottom_right.col &&
+            coord.row >= top_left.row && coord.row <= bottom_right.row {
+            rows[(coord.row - top_left.row) as usize].push(coord.col - top_left.col);
+        }
+    }
+
+    for mut cols in rows {
+
+        cols.sort();
+
+        let mut line = match cols.last() {
+            Some(&last) => vec!['.'; last as usize + 1],
+            None => vec!['.'],
+        };
+
+        for col in cols {
+            line[col as usize] = 'O';
+        }
+
+        result.extend(line);
+        result.push('\n');
+
+    }
+
+    result
+
+}
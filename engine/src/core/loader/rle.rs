@@ -0,0 +1,647 @@
+use std::collections::BTreeMap;
+use std::iter::{FromIterator, Peekable};
+
+use super::{Metadata, ParseError, Pattern, MAX_COORD, MAX_CELLS, parse_rule, format_rule, parse_coords};
+use super::super::{Coord, Rule};
+use board::{Board, CellDesc, Grid};
+use core::table::MAX_STATES;
+
+// maximum length of RLE-encoded line as recommended by the format description
+const MAX_LINE_LENGTH: usize = 70;
+
+
+#[derive(Debug, PartialEq)]
+enum Lexem {
+    ParamName(String),
+    ValueNumeric(isize),
+    ValueString(String),
+    Comma,
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    rule: Option<Rule>,
+    grid: Option<Grid>,
+}
+
+
+fn get_str<T>(it: &mut Peekable<T>) -> String
+
+    where T: Iterator<Item=char> {
+
+    let mut result = String::new();
+
+    loop {
+        match it.peek().cloned() {
+            Some(c) => {
+                if c != '=' {
+                    result.push(c);
+                } else {
+                    break
+                }
+                it.next();
+            }
+            None => break,
+        }
+    }
+
+    result
+
+}
+
+fn get_num<T>(it: &mut Peekable<T>, end_col: usize) -> Result<isize, ParseError>
+        where T: Iterator<Item=(usize, char)> {
+
+    let mut digits = String::new();
+
+    let first_col = match it.peek().cloned() {
+
+        Some((col, c)) => {
+            if !c.is_digit(10) {
+                return Err(ParseError::NotANumber(String::from_iter(it.map(|(_, c)| c)), 0, col));
+            }
+            col
+        },
+        None => return Err(ParseError::InputExhausted(0, end_col)),
+
+    };
+
+    loop {
+
+        match it.peek().cloned() {
+            Some((_, c)) => {
+                if c.is_digit(10) {
+                    digits.push(c);
+                    it.next();
+                } else {
+                    break;
+                }
+            }
+            None => break
+        }
+
+    }
+
+    // too large numbers are not numbers either
+    digits.parse::<isize>().map_err(|_| ParseError::NotANumber(digits.clone(), 0, first_col))
+
+}
+
+fn get_value_str<T>(it: &mut Peekable<T>, end_col: usize) -> Result<String, ParseError>
+        where T: Iterator<Item=(usize, char)> {
+
+    // string value takes the rest of the line since it may contain commas,
+    // e.g. "rule = B3/S23:T10,10", so it must be the last parameter
+
+    let result = String::from_iter(it.map(|(_, c)| c));
+
+    if result.is_empty() {
+        return Err(ParseError::InputExhausted(0, end_col));
+    }
+
+    Ok(result)
+
+}
+
+const CHARS_TO_FILTER: [char; 3] = [' ', '\n', '\r'];
+
+fn contains(c: char, arr: &[char]) -> bool {
+    arr.iter().position(|&x| c == x).is_some()
+}
+
+fn filter_line<'a>(line: &'a str) -> Box<Iterator<Item=(usize, char)> + 'a> {
+    // filter redundant chars, the rest are kept with their columns
+    Box::new(line.chars().enumerate().map(|(idx, c)| (idx + 1, c)).filter(|&(_, c)| !contains(c, &CHARS_TO_FILTER[..])))
+}
+
+fn parse_comment(line: &str, metadata: &mut Metadata, offset: &mut Option<Coord>) -> Result<(), ParseError> {
+
+    // #N - name of a pattern
+    // #O - author
+    // #C or #c - comment
+    // #P or #R - position of the top left corner
+    // other lines are ignored
+
+    let text = line.get(2..).unwrap_or("").trim();
+
+    match line.get(..2) {
+        Some("#N") => metadata.name = Some(String::from(text)),
+        Some("#O") => metadata.author = Some(String::from(text)),
+        Some("#C") | Some("#c") => metadata.comments.push(String::from(text)),
+        Some("#P") | Some("#R") => *offset = Some(parse_coords(line, text, 0)?),
+        _ => {},
+    }
+
+    Ok(())
+}
+
+// decoder of RLE data, it's fed with characters one by one, so runs,
+// row skips and state prefixes may span several lines
+struct Decoder {
+    row: isize,
+    col: isize,
+    // run count read so far and the position of its first digit
+    repeat: Option<(isize, usize, usize)>,
+    // p to y prefix of states above 24 and its position
+    prefix: Option<(char, usize, usize)>,
+    // set once the ! terminator is read, the rest of input is ignored
+    finished: bool,
+
+    coords: Vec<Coord>,
+    states: Vec<usize>,
+}
+
+impl Decoder {
+
+    fn new() -> Self {
+        Decoder {
+            row: 0,
+            col: 0,
+            repeat: None,
+            prefix: None,
+            finished: false,
+
+            coords: Vec::new(),
+            states: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, c: char, line_no: usize, col_no: usize) -> Result<(), ParseError> {
+
+        // b - dead cell
+        // o - alive cell
+        // $ - end of row, a run of them skips empty rows
+        // ! - end of pattern
+        // multi-state patterns use . for dead cells, A to X for states
+        // 1 to 24 and pA to yO for states 25 to 255
+
+        if self.finished || c.is_whitespace() {
+            return Ok(());
+        }
+
+        let state = match (c, self.prefix) {
+
+            ('0'..='9', None) => {
+                // longer runs would move cells too far from the origin anyway
+                let (repeat, first_line, first_col) = self.repeat.unwrap_or((0, line_no, col_no));
+
+                let repeat = repeat.checked_mul(10).
+                    and_then(|repeat| repeat.checked_add(c.to_digit(10).unwrap() as isize)).
+                    filter(|&repeat| repeat <= MAX_COORD);
+
+                match repeat {
+                    Some(repeat) => self.repeat = Some((repeat, first_line, first_col)),
+                    None => return Err(ParseError::UnexpectedSymbol(c.to_string(), line_no, col_no)),
+                }
+
+                return Ok(());
+            },
+
+            ('p'..='y', None) => {
+                self.prefix = Some((c, line_no, col_no));
+                return Ok(());
+            },
+
+            ('$', None) => {
+                self.row += self.repeat.take().map_or(1, |(repeat, _, _)| repeat);
+                self.col = 0;
+
+                if self.row > MAX_COORD {
+                    return Err(ParseError::UnexpectedSymbol(c.to_string(), line_no, col_no));
+                }
+
+                return Ok(());
+            },
+
+            ('!', None) => {
+                // run count before the terminator means nothing
+                self.repeat = None;
+                self.finished = true;
+                return Ok(());
+            },
+
+            ('b', None) | ('.', None) => 0,
+            ('o', None) => 1,
+
+            ('A'..='X', _) => {
+                let high = self.prefix.map_or(0, |(prefix, _, _)| prefix as usize - 'p' as usize + 1);
+                let state = 24 * high + c as usize - 'A' as usize + 1;
+                if state >= MAX_STATES {
+                    return Err(ParseError::UnknownCell(c, line_no, col_no));
+                }
+                self.prefix = None;
+                state
+            },
+
+            _ => return Err(ParseError::UnknownCell(c, line_no, col_no)),
+        };
+
+        let (count, first_line, first_col) = self.repeat.take().unwrap_or((1, line_no, col_no));
+
+        if self.col + count > MAX_COORD {
+            return Err(ParseError::UnexpectedSymbol(c.to_string(), line_no, col_no));
+        }
+
+        // long runs of live cells wouldn't fit into memory, dead ones only move the column
+        if state != 0 && self.coords.len() + count as usize > MAX_CELLS {
+            return Err(ParseError::UnexpectedSymbol(count.to_string(), first_line, first_col));
+        }
+
+        if state != 0 {
+            for col in self.col..self.col + count {
+                self.coords.push(Coord { col, row: self.row });
+                self.states.push(state);
+            }
+        }
+
+        self.col += count;
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(Vec<Coord>, Vec<usize>), ParseError> {
+        match self.prefix {
+            // prefix without a state letter
+            Some((prefix, line_no, col_no)) => Err(ParseError::UnknownCell(prefix, line_no, col_no)),
+            None => Ok((self.coords, self.states)),
+        }
+    }
+
+}
+
+fn lexer(line: &str) -> Result<Vec<(usize, Lexem)>, ParseError> {
+
+    // lexems are returned with their columns, errors have the column of
+    // the symbol they were found at, line is set by the caller
+
+    let mut result: Vec<(usize, Lexem)> = Vec::new();
+    let mut prefix: Vec<(usize, char)> = Vec::new();
+
+    let end_col = line.chars().count() + 1;
+    let mut it = filter_line(line).peekable();
+
+    while let Some((col, c)) = it.next() {
+
+        prefix.push((col, c));
+
+        match(c) {
+
+            '=' => {
+
+                // read param name
+                let name = get_str(&mut prefix.iter().map(|&(_, c)| c).peekable());
+
+                // ensure parameter name is valid, i.e.
+                if name.is_empty() {
+                    // it's not empty
+                    return Err(ParseError::EmptyName(0, col));
+                } else if !name.chars().all(|c| c.is_alphabetic())  {
+                    // and contains only alphabetic characters
+                    return Err(ParseError::WrongName(prefix.iter().map(|&(_, c)| c).collect(), 0, prefix[0].0));
+                }
+
+                result.push((prefix[0].0, Lexem::ParamName(name.clone())));
+                prefix.clear();
+
+                // read param value, rule is the only non-numeric parameter
+                let value_col = it.peek().map_or(end_col, |&(col, _)| col);
+
+                if name == "rule" {
+                    result.push((value_col, Lexem::ValueString(get_value_str(&mut it, end_col)?)));
+                } else {
+                    result.push((value_col, Lexem::ValueNumeric(get_num(&mut it, end_col)?)));
+                }
+
+            },
+
+            ',' => {
+                result.push((col, Lexem::Comma));
+                prefix.clear();
+            },
+
+            _ => { }
+
+        }
+
+    }
+
+    Ok(result)
+
+}
+
+fn parse_header(lexems: Vec<(usize, Lexem)>) -> Result<Header, ParseError> {
+
+    // errors point to the lexem they were found at, missing
+    // parameters point to the start of the header
+
+    let mut width: Option<usize> = None;
+    let mut height: Option<usize> = None;
+    let mut rule: Option<Rule> = None;
+    let mut grid: Option<Grid> = None;
+
+    let mut it = lexems.into_iter();
+
+    while let Some((col, lexem)) = it.next() {
+
+        match lexem {
+
+            Lexem::ParamName(name) => {
+
+                match (name.as_str(), it.next()) {
+
+                    // sizes are limited as well as coordinates of cells
+                    ("x", Some((_, Lexem::ValueNumeric(value)))) if value <= MAX_COORD => width = Some(value as usize),
+                    ("y", Some((_, Lexem::ValueNumeric(value)))) if value <= MAX_COORD => height = Some(value as usize),
+
+                    ("x", Some((value_col, Lexem::ValueNumeric(value)))) |
+                    ("y", Some((value_col, Lexem::ValueNumeric(value)))) => {
+                        return Err(ParseError::UnexpectedSymbol(value.to_string(), 0, value_col));
+                    },
+
+                    ("rule", Some((value_col, Lexem::ValueString(value)))) => {
+                        let (parsed_rule, parsed_grid) = parse_rule(&value, 0, value_col)?;
+                        rule = Some(parsed_rule);
+                        grid = parsed_grid;
+                    },
+
+                    (_, _) => return Err(ParseError::WrongName(name.clone(), 0, col)),
+                }
+
+            },
+
+            Lexem::Comma => {},
+
+            Lexem::ValueNumeric(value) => return Err(ParseError::UnexpectedSymbol(value.to_string(), 0, col)),
+            Lexem::ValueString(value) => return Err(ParseError::UnexpectedSymbol(value, 0, col)),
+        }
+
+    }
+
+    Ok(Header {
+        width: width.ok_or(ParseError::MissingParam(String::from("x"), 0, 1))?,
+        height: height.ok_or(ParseError::MissingParam(String::from("y"), 0, 1))?,
+        rule,
+        grid,
+    })
+
+}
+
+pub fn parse_stream<T>(mut lines: T) -> Result<Pattern, ParseError>
+            where T: Iterator<Item=String> {
+
+    let mut header: Option<Header> = None;
+
+    let mut metadata = Metadata::default();
+    let mut offset: Option<Coord> = None;
+
+    let mut line_no = 0;
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        if line.starts_with('#') {
+            parse_comment(&line, &mut metadata, &mut offset).map_err(|err| err.at_line(line_no))?;
+            continue;
+        } else if line.trim().is_empty() {
+            continue;
+        } else {
+            // read header data
+            let lexems = lexer(&line[..]).map_err(|err| err.at_line(line_no))?;
+            header = Some(parse_header(lexems).map_err(|err| err.at_line(line_no))?);
+            break;
+        }
+
+    }
+
+    let header = header.ok_or(ParseError::InputExhausted(line_no + 1, 1))?;
+
+    // parse RLE-encoded data
+    let mut decoder = Decoder::new();
+
+    for line in &mut lines {
+
+        line_no += 1;
+
+        // comment lines may appear between lines of data as well
+        if line.starts_with('#') {
+            parse_comment(&line, &mut metadata, &mut offset).map_err(|err| err.at_line(line_no))?;
+            continue;
+        }
+
+        for (idx, c) in line.chars().enumerate() {
+            decoder.push(c, line_no, idx + 1)?;
+        }
+
+        if decoder.finished {
+            break;
+        }
+    }
+
+    let (coords, states) = decoder.finish()?;
+
+    Ok(Pattern {
+        width: header.width,
+        height: header.height,
+        rule: header.rule,
+        grid: header.grid,
+        coords,
+        states,
+        metadata,
+        offset,
+    })
+
+}
+
+
+struct LineWrapper {
+    result: String,
+    line_len: usize,
+}
+
+impl LineWrapper {
+
+    fn new() -> Self {
+        LineWrapper { result: String::new(), line_len: 0 }
+    }
+
+    fn push_run(&mut self, cnt: isize, tag: &str) {
+
+        // runs are never split between lines
+
+        let token = if cnt == 1 { tag.to_string() } else { format!("{}{}", cnt, tag) };
+
+        if self.line_len + token.len() > MAX_LINE_LENGTH {
+            self.result.push('\n');
+            self.line_len = 0;
+        }
+
+        self.line_len += token.len();
+        self.result.push_str(&token);
+    }
+
+}
+
+fn state_tag(state: usize, multistate: bool) -> String {
+
+    // two state patterns use b and o, multi-state ones use . for
+    // empty cells, A to X for states 1 to 24 and pA to yO above
+
+    let letter = |n: usize| ::std::char::from_u32('A' as u32 + n as u32).unwrap();
+
+    match state {
+        0 if multistate => String::from("."),
+        0 => String::from("b"),
+        1 if !multistate => String::from("o"),
+        1..=24 => letter(state - 1).to_string(),
+        _ => {
+            let prefix = ::std::char::from_u32('p' as u32 + (state as u32 - 25) / 24).unwrap();
+            format!("{}{}", prefix, letter((state - 25) % 24))
+        }
+    }
+}
+
+pub fn encode(board: &Board, rule: &Rule, metadata: &Metadata, offset: Option<Coord>,
+              top_left: Coord, bottom_right: Coord) -> String {
+
+    // cells in states above 1 are saved only for multi-state rules
+    let multistate = rule.get_states() > 2;
+
+    // group non empty cells inside the region by rows, rows and
+    // columns must be in ascending order
+    let mut rows: BTreeMap<isize, Vec<(isize, usize)>> = BTreeMap::new();
+
+    for CellDesc { coord, state, .. } in board.iter() {
+        if (state == 1 || (multistate && state > 1)) &&
+            coord.col >= top_left.col && coord.col <= bottom_right.col &&
+            coord.row >= top_left.row && coord.row <= bottom_right.row {
+            rows.entry(coord.row).or_default().push((coord.col, state));
+        }
+    }
+
+    let (width, height) = if top_left.col <= bottom_right.col && top_left.row <= bottom_right.row {
+        (bottom_right.col - top_left.col + 1, bottom_right.row - top_left.row + 1)
+    } else {
+        (0, 0)
+    };
+
+    let mut wrapper = LineWrapper::new();
+    let mut cur_row = top_left.row;
+
+    for (row, mut cells) in rows {
+
+        cells.sort();
+
+        // skip to the current row, several empty rows are encoded as a single run
+        if row > cur_row {
+            wrapper.push_run(row - cur_row, "$");
+            cur_row = row;
+        }
+
+        let mut cur_col = top_left.col;
+        let mut idx = 0;
+
+        while idx < cells.len() {
+
+            // find the end of a run of consecutive cells in the same state
+            let (run_start, state) = cells[idx];
+            while idx + 1 < cells.len() && cells[idx + 1] == (cells[idx].0 + 1, state) {
+                idx += 1;
+            }
+
+            if run_start > cur_col {
+                wrapper.push_run(run_start - cur_col, &state_tag(0, multistate));
+            }
+
+            wrapper.push_run(cells[idx].0 - run_start + 1, &state_tag(state, multistate));
+
+            // dead cells at the end of a row are not encoded
+            cur_col = cells[idx].0 + 1;
+            idx += 1;
+        }
+    }
+
+    wrapper.push_run(1, "!");
+
+    let rule = format_rule(rule, board.get_grid());
+
+    let mut comments = String::new();
+
+    if let Some(ref name) = metadata.name {
+        comments.push_str(&format!("#N {}\n", name));
+    }
+
+    if let Some(ref author) = metadata.author {
+        comments.push_str(&format!("#O {}\n", author));
+    }
+
+    for comment in metadata.comments.iter() {
+        comments.push_str(&format!("#C {}\n", comment));
+    }
+
+    if let Some(Coord { col, row }) = offset {
+        comments.push_str(&format!("#R {} {}\n", col, row));
+    }
+
+    format!("{}x = {}, y = {}, rule = {}\n{}\n", comments, width, height, rule, wrapper.result)
+
+}
+
+
+#[cfg(test)]
+fn lexems(line: &str) -> Vec<Lexem> {
+    lexer(line).unwrap().into_iter().map(|(_, lexem)| lexem).collect()
+}
+
+#[test]
+fn test_lexer_param() {
+    assert!(lexems("x =  25\ny = 30") ==
+        vec![Lexem::ParamName(String::from("x")), Lexem::ValueNumeric(25),
+             Lexem::ParamName(String::from("y")), Lexem::ValueNumeric(30)]);
+}
+
+#[test]
+fn test_lexer_param_comma_sep() {
+    assert!(lexems("x =  25 ,   y = 30") ==
+        vec![Lexem::ParamName(String::from("x")), Lexem::ValueNumeric(25), Lexem::Comma,
+             Lexem::ParamName(String::from("y")), Lexem::ValueNumeric(30)]);
+}
+
+#[test]
+#[should_panic]
+fn test_lexer_error1() {
+    lexer("x =  a25\ny = 30").unwrap();
+}
+
+#[test]
+#[should_panic]
+fn test_lexer_error2() {
+    lexer("x =  ").unwrap();
+}
+
+#[test]
+#[should_panic]
+fn test_lexer_error3() {
+    lexer("=  25").unwrap();
+}
+
+#[test]
+fn test_lexer_rule() {
+    assert!(lexems("x = 3, y = 3, rule = B36/S23") ==
+        vec![Lexem::ParamName(String::from("x")), Lexem::ValueNumeric(3), Lexem::Comma,
+             Lexem::ParamName(String::from("y")), Lexem::ValueNumeric(3), Lexem::Comma,
+             Lexem::ParamName(String::from("rule")), Lexem::ValueString(String::from("B36/S23"))]);
+}
+
+#[test]
+fn test_state_tags() {
+    assert_eq!(state_tag(0, false), "b");
+    assert_eq!(state_tag(1, false), "o");
+    assert_eq!(state_tag(0, true), ".");
+    assert_eq!(state_tag(1, true), "A");
+    assert_eq!(state_tag(24, true), "X");
+    assert_eq!(state_tag(25, true), "pA");
+    assert_eq!(state_tag(49, true), "qA");
+    assert_eq!(state_tag(255, true), "yO");
+}
@@ -1,28 +1,77 @@
 extern crate rand;
 extern crate time;
 
-use board::{Board, CellDesc, HashedBoard, SymVecBoard, Coord};
+use board::{Board, Cell, CellDesc, HashedBoard, SymVecBoard, BitPackedBoard, Coord, Grid, Topology};
+use board::transform::{self, StampMode, Transform};
+use self::hashlife::HashLife;
+use self::analysis::PeriodDetector;
+use self::history::{History, Edit, Snapshot};
+use self::rewind::{Rewind, Delta};
+use self::loader::{Metadata, Pattern};
 
 use self::rand::distributions::{IndependentSample, Range};
 
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::thread;
 
 const SWITCH_BOARD_INERTIA: usize = 128;
+// boards with lower density are switched to hashed representation
+const SPARSE_BOARD_DENSITY: f64 = 0.03;
+// boards with higher density are switched to bit packed representation
+const DENSE_BOARD_DENSITY: f64 = 0.1;
 const ITERATIONS_TO_CLEANUP: usize = 1000;
+// boards with less cells are always processed in a single thread
+const PARALLEL_MIN_CELLS: usize = 4096;
 
 pub mod loader;
+pub mod rule;
+pub mod analysis;
+mod hashlife;
+mod history;
+mod rewind;
+mod neighbourhood;
+mod hensel;
+mod table;
+
+pub use self::rule::{Rule, RuleError};
+pub use self::neighbourhood::{Neighbourhood, Shape};
+pub use self::table::RuleTable;
+pub use self::analysis::Periodicity;
 
 
 #[derive(PartialEq, Copy, Clone)]
 enum BoardType {
     Hashed,
-    SymVec
+    SymVec,
+    BitPacked
 }
 
 pub struct Engine {
-    cols: Option<usize>,
-    rows: Option<usize>,
+    // board sizes and topology
+    grid: Grid,
+
+    rule: Rule,
+
+    // HashLife universe is kept between jumps to reuse memoized results
+    hashlife: Option<HashLife>,
+
+    // number of threads used to compute the next generation
+    threads: usize,
+
+    // detects when the pattern becomes still, oscillating or moving,
+    // generations are observed only if detection is enabled
+    detector: PeriodDetector,
+    detect_periods: bool,
+
+    // edits of the board that can be undone
+    history: History,
+
+    // recently computed generations to step back through
+    rewind: Rewind,
+
+    // name, author and comments of the loaded pattern
+    metadata: Metadata,
 
     board_type: BoardType,
     iters_from_prev_switch: usize,
@@ -39,17 +88,32 @@ struct MinMax {
 
 impl Engine {
 
-    pub fn new(config_vec: Option<Vec<Coord>>, cols: Option<usize>, rows: Option<usize>) -> Self {
+    pub fn new(config_vec: Option<Vec<Coord>>, cols: Option<usize>, rows: Option<usize>,
+               rule: Rule) -> Self {
 
         let board_type = BoardType::Hashed;
 
         let mut engine = Engine {
-            cols: cols,
-            rows: rows,
+            grid: Grid::new(Topology::default(), cols, rows),
+
+            rule,
+
+            hashlife: None,
+
+            threads: 1,
+
+            detector: PeriodDetector::new(),
+            detect_periods: false,
+
+            history: History::new(),
+
+            rewind: Rewind::new(0),
+
+            metadata: Metadata::default(),
 
             board_type: board_type,
             iters_from_prev_switch: SWITCH_BOARD_INERTIA,
-            board: Self::new_board(board_type, cols, rows),
+            board: Self::new_board(board_type, Grid::new(Topology::default(), cols, rows)),
             iteration: 0,
             last_iter_time: 0f64
         };
@@ -63,37 +127,52 @@ impl Engine {
 
     pub fn set_predefined(&mut self, board_config: Vec<Coord>) {
         self.board.set_predefined(board_config);
+        self.forget_past();
     }
 
     pub fn reset(&mut self) {
-        self.board = Self::new_board(self.board_type, self.cols, self.rows);
+        self.board = Self::new_board(self.board_type, self.grid);
         self.iteration = 0;
         self.last_iter_time = 0f64;
+        self.metadata = Metadata::default();
+        self.forget_past();
     }
 
-    fn new_board(board_type: BoardType, cols: Option<usize>, rows: Option<usize>) -> Board {
-        if board_type == BoardType::Hashed {
-            Board::new(HashedBoard::new(), cols, rows)
-        }  else {
-            Board::new(SymVecBoard::new(), cols, rows)
+    fn new_board(board_type: BoardType, grid: Grid) -> Board {
+        match board_type {
+            BoardType::Hashed => Board::with_grid(HashedBoard::new(), grid),
+            BoardType::SymVec => Board::with_grid(SymVecBoard::new(), grid),
+            BoardType::BitPacked => Board::with_grid(BitPackedBoard::new(), grid),
         }
     }
 
     fn clone_board(&self, board_type: BoardType) -> Board {
 
-        let mut new_board = Self::new_board(board_type,
-                                            self.board.get_cols(), self.board.get_rows());
+        let mut new_board = Self::new_board(board_type, self.grid);
 
-        for CellDesc { coord, gen, is_alive, .. } in self.board.iter() {
-            if is_alive {
-                new_board.born_at_gen(coord.col, coord.row, gen);
-            }
+        for (coord, cell) in self.board.get_cells() {
+            new_board.set_cell(coord.col, coord.row, cell);
         }
 
         new_board
 
     }
 
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        // memoized results are valid only for the rule they were computed with
+        self.hashlife = None;
+        self.forget_past();
+
+        if self.board_type == BoardType::BitPacked && !self.can_use_packed_board() {
+            self.switch_board_to(BoardType::SymVec);
+        }
+    }
+
     pub fn cur_iteration(&self) -> usize {
         self.iteration
     }
@@ -107,8 +186,7 @@ impl Engine {
     }
 
     pub fn from_coord_vec(&self, coords: Vec<Coord>) -> Board {
-        let mut board = Self::new_board(self.board_type,
-                                        self.board.get_cols(), self.board.get_rows());
+        let mut board = Self::new_board(self.board_type, self.grid);
 
         for Coord{col, row} in coords {
             board.born_at(col, row);
@@ -117,18 +195,432 @@ impl Engine {
         board
     }
 
+    fn pattern_cell(&self, state: usize) -> Cell {
+
+        // cells in states above 1 are cells of a rule table or
+        // dying cells of Generations rules
+
+        match state {
+            1 => Cell::Occupied { gen: 1 },
+            _ if self.rule.get_table().is_some() => Cell::State { state },
+            _ => Cell::Dying { state },
+        }
+    }
+
+    pub fn from_pattern(&self, pattern: &Pattern) -> Board {
+
+        // the pattern is moved to its offset if it's given
+
+        let mut board = Self::new_board(self.board_type, self.grid);
+
+        let Coord { col: dx, row: dy } = pattern.offset.unwrap_or(Coord { col: 0, row: 0 });
+
+        for (&Coord { col, row }, &state) in pattern.coords.iter().zip(pattern.states.iter()) {
+            board.set_cell(col + dx, row + dy, self.pattern_cell(state));
+        }
+
+        board
+    }
+
+    pub fn get_metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = metadata;
+    }
+
     pub fn set_board(&mut self, board: Board) {
         self.board = board;
+        self.forget_past();
     }
 
     pub fn get_board_mut(&mut self) -> &mut Board {
+        // board may be edited, so previous generations are not valid any more
+        self.forget_past();
         &mut self.board
     }
 
+    pub fn get_grid(&self) -> Grid {
+        self.grid
+    }
+
+    pub fn set_grid(&mut self, grid: Grid) {
+
+        // change board sizes and topology, live cells are moved
+        // to the new board, cells beyond its edges are lost
+
+        self.grid = grid;
+
+        if self.board_type == BoardType::BitPacked && !self.can_use_packed_board() {
+            self.board_type = BoardType::SymVec;
+        }
+
+        let new_board = self.clone_board(self.board_type);
+        self.set_board(new_board);
+    }
+
+    pub fn set_cell(&mut self, col: isize, row: isize, alive: bool) {
+
+        // make a cell alive or dead recording the change in the history
+
+        if self.board.is_alive(col, row) == alive {
+            return;
+        }
+
+        let before = self.board.get_cell(col, row);
+
+        if alive {
+            self.board.born_at(col, row);
+        } else {
+            self.board.kill_at(col, row);
+        }
+
+        let after = self.board.get_cell(col, row);
+
+        self.history.push(Edit::Cells(vec![(Coord { col, row }, before, after)]));
+        self.forget_past();
+    }
+
+    pub fn begin_edit(&mut self) {
+        // cells changed until end_edit is called are undone at once
+        self.history.begin_group();
+    }
+
+    pub fn end_edit(&mut self) {
+        self.history.end_group();
+    }
+
+    pub fn replace_board(&mut self, board: Board) {
+        // same as set_board, but previous board can be restored with undo,
+        // the new board isn't a loaded pattern, so its metadata is dropped
+        let before = self.snapshot();
+        self.set_board(board);
+        self.metadata = Metadata::default();
+        let after = self.snapshot();
+
+        self.history.push(Edit::Board { before: Box::new(before), after: Box::new(after) });
+    }
+
+    pub fn clear(&mut self) {
+        // same as reset, but previous board can be restored with undo
+        let before = self.snapshot();
+        self.reset();
+        let after = self.snapshot();
+
+        self.history.push(Edit::Board { before: Box::new(before), after: Box::new(after) });
+    }
+
+    pub fn load_pattern(&mut self, pattern: &Pattern) {
+
+        // replace the board with the pattern, its rule and topology are
+        // used if they are given, previous board can be restored with undo
+
+        let before = self.snapshot();
+
+        if let Some(grid) = pattern.grid {
+            self.set_grid(grid);
+        }
+
+        if let Some(ref rule) = pattern.rule {
+            self.set_rule(rule.clone());
+        }
+
+        let board = self.from_pattern(pattern);
+        self.set_board(board);
+        self.metadata = pattern.metadata.clone();
+
+        let after = self.snapshot();
+
+        self.history.push(Edit::Board { before: Box::new(before), after: Box::new(after) });
+    }
+
+    pub fn stamp(&mut self, pattern: &Pattern, offset: Coord, mode: StampMode) {
+
+        // place the top left corner of the pattern at the offset combining
+        // its cells with cells of the board, the offset of the pattern itself
+        // is ignored, the change can be reverted with undo
+
+        let Coord { col: dx, row: dy } = offset;
+
+        let cells: HashMap<Coord, Cell> = pattern.coords.iter().zip(pattern.states.iter()).
+            map(|(&Coord { col, row }, &state)| (Coord { col: col + dx, row: row + dy }, self.pattern_cell(state))).
+            collect();
+
+        // cells may lie beyond the sizes given in the header
+        let (width, height) = cells.keys().fold((pattern.width as isize, pattern.height as isize), |(w, h), c| {
+            (w.max(c.col - dx + 1), h.max(c.row - dy + 1))
+        });
+
+        let inside = |coord: &Coord| coord.col >= dx && coord.col < dx + width &&
+                                     coord.row >= dy && coord.row < dy + height;
+
+        let mut changed: Vec<(Coord, Cell)> = Vec::new();
+
+        match mode {
+            StampMode::Or => {
+                for (&coord, &cell) in cells.iter() {
+                    if !self.board.is_alive(coord.col, coord.row) {
+                        changed.push((coord, cell));
+                    }
+                }
+            },
+            StampMode::Xor => {
+                for (&coord, &cell) in cells.iter() {
+                    if self.board.is_alive(coord.col, coord.row) {
+                        changed.push((coord, Cell::Empty));
+                    } else {
+                        changed.push((coord, cell));
+                    }
+                }
+            },
+            StampMode::And | StampMode::Overwrite => {
+                // cells of the board inside the rectangle which are not in the pattern are cleared
+                for CellDesc { coord, state, .. } in self.board.iter() {
+                    if state != 0 && inside(&coord) && !cells.contains_key(&coord) {
+                        changed.push((coord, Cell::Empty));
+                    }
+                }
+
+                if mode == StampMode::Overwrite {
+                    changed.extend(cells.iter().map(|(&coord, &cell)| (coord, cell)));
+                }
+            },
+        }
+
+        self.change_cells(changed);
+    }
+
+    pub fn clear_region(&mut self, top_left: Coord, bottom_right: Coord, inside: bool) {
+
+        // kill all cells inside or outside the rectangle, both corners
+        // are included into it, the change can be reverted with undo
+
+        let changed = self.board.iter().
+            filter(|desc| desc.state != 0 && in_region(desc.coord, top_left, bottom_right) == inside).
+            map(|desc| (desc.coord, Cell::Empty)).collect();
+
+        self.change_cells(changed);
+    }
+
+    pub fn fill_random(&mut self, top_left: Coord, bottom_right: Coord, p: f64) {
+
+        // replace cells of the rectangle with random ones, each cell is
+        // alive with probability p, the change can be reverted with undo
+
+        let between = Range::new(0f64, 1.);
+        let mut rng = rand::thread_rng();
+
+        // cells beyond edges of a bounded board are either skipped or
+        // wrapped around, so every cell is changed only once
+        let mut cells: HashMap<Coord, Cell> = HashMap::new();
+
+        for row in top_left.row..bottom_right.row + 1 {
+            for col in top_left.col..bottom_right.col + 1 {
+                if let Some((col, row)) = self.board.constrain_board(col, row) {
+                    let cell = if between.ind_sample(&mut rng) < p { self.pattern_cell(1) } else { Cell::Empty };
+                    cells.insert(Coord { col, row }, cell);
+                }
+            }
+        }
+
+        self.change_cells(cells.into_iter().collect());
+    }
+
+    pub fn transform_region(&mut self, top_left: Coord, bottom_right: Coord, transform: Transform) -> (Coord, Coord) {
+
+        // rotate or flip cells of the rectangle in place keeping its top left
+        // corner, cells covered by the transformed rectangle are replaced,
+        // returns its corners, the change can be reverted with undo
+
+        let new_bottom_right = if transform.swaps_sides() {
+            Coord { col: top_left.col + bottom_right.row - top_left.row,
+                    row: top_left.row + bottom_right.col - top_left.col }
+        } else {
+            bottom_right
+        };
+
+        let (coords, cells): (Vec<Coord>, Vec<Cell>) = self.board.get_cells().into_iter().
+            filter(|&(coord, _)| in_region(coord, top_left, bottom_right)).unzip();
+
+        // both rectangles are cleared first
+        let mut changed: HashMap<Coord, Cell> = self.board.get_cells().into_iter().
+            filter(|&(coord, _)| in_region(coord, top_left, bottom_right) ||
+                                 in_region(coord, top_left, new_bottom_right)).
+            map(|(coord, _)| (coord, Cell::Empty)).collect();
+
+        let moved = transform::transform_region(&coords, transform, top_left, bottom_right);
+        changed.extend(moved.into_iter().zip(cells));
+
+        self.change_cells(changed.into_iter().collect());
+
+        (top_left, new_bottom_right)
+    }
+
+    fn change_cells(&mut self, changed: Vec<(Coord, Cell)>) {
+
+        // set cells recording only the ones that actually change,
+        // they are undone at once
+
+        let mut changes = Vec::new();
+
+        for (coord, cell) in changed {
+            let before = self.board.get_cell(coord.col, coord.row);
+
+            if before != cell {
+                self.board.set_cell(coord.col, coord.row, cell);
+                changes.push((coord, before, self.board.get_cell(coord.col, coord.row)));
+            }
+        }
+
+        if !changes.is_empty() {
+            self.history.push(Edit::Cells(changes));
+            self.forget_past();
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        // revert the last edit, returns false if there is nothing to undo
+        match self.history.undo() {
+            Some(edit) => {
+                self.apply_edit(edit);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        // repeat the last reverted edit, returns false if there is nothing to redo
+        match self.history.redo() {
+            Some(edit) => {
+                self.apply_edit(edit);
+                true
+            },
+            None => false
+        }
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn clear_history(&mut self) {
+        // edits made so far can't be undone any more
+        self.history.clear();
+    }
+
+    fn apply_edit(&mut self, edit: Edit) {
+
+        match edit {
+
+            Edit::Cells(changes) => {
+                for (coord, _, cell) in changes {
+                    self.board.set_cell(coord.col, coord.row, cell);
+                }
+            },
+
+            Edit::Board { after, .. } => self.restore(*after),
+        }
+
+        self.forget_past();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.board.get_cells(),
+            iteration: self.iteration,
+            rule: self.rule.clone(),
+            grid: self.grid,
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+
+        if snapshot.grid != self.grid {
+            self.set_grid(snapshot.grid);
+        }
+
+        if snapshot.rule != self.rule {
+            self.set_rule(snapshot.rule);
+        }
+
+        let mut board = Self::new_board(self.board_type, self.grid);
+
+        for (coord, cell) in snapshot.cells {
+            board.set_cell(coord.col, coord.row, cell);
+        }
+
+        self.set_board(board);
+        self.iteration = snapshot.iteration;
+        self.metadata = snapshot.metadata;
+    }
+
+    fn forget_past(&mut self) {
+        // board was changed not by computing a generation, so
+        // previous generations are not valid any more
+        self.detector.reset();
+        self.rewind.clear();
+    }
+
+    pub fn set_rewind_capacity(&mut self, generations: usize) {
+        // number of generations to keep for stepping back, 0 disables rewind
+        self.rewind.set_capacity(generations);
+    }
+
+    pub fn can_step_back(&self) -> bool {
+        self.rewind.len() > 0
+    }
+
+    pub fn step_back(&mut self) -> bool {
+
+        // restore the previous generation, returns false if it wasn't recorded
+
+        let delta = match self.rewind.pop() {
+            Some(delta) => delta,
+            None => return false
+        };
+
+        let mut board = Self::new_board(self.board_type, self.grid);
+
+        for (coord, cell) in delta.revert(&self.board) {
+            board.set_cell(coord.col, coord.row, cell);
+        }
+
+        self.board = board;
+        self.iteration = self.iteration.saturating_sub(1);
+
+        // generations are counted from the past now
+        self.detector.reset();
+
+        true
+    }
+
+    pub fn get_periodicity(&self) -> Option<Periodicity> {
+        self.detector.get_result()
+    }
+
+    pub fn set_period_detection(&mut self, enabled: bool) {
+
+        // every observed generation is normalized and remembered,
+        // so it's done only for callers which need periodicity
+
+        if self.detect_periods != enabled {
+            self.detect_periods = enabled;
+            self.detector.reset();
+        }
+    }
+
     pub fn create_random(&self, p: f64) -> Board {
 
-        let mut board = Self::new_board(self.board_type,
-                                        self.board.get_cols(), self.board.get_rows());
+        let mut board = Self::new_board(self.board_type, self.grid);
 
         let cols = self.board.get_cols();
         let rows = self.board.get_rows();
@@ -136,12 +628,16 @@ impl Engine {
         let between = Range::new(0f64, 1.);
         let mut rng = rand::thread_rng();
 
-        if cols.is_some() && rows.is_some() {
-            for col in 0..cols.unwrap() {
-                for row in 0..rows.unwrap() {
+        if let (Some(cols), Some(rows)) = (cols, rows) {
+            // board spans cells from -size / 2 to size - size / 2 - 1
+            let left = (cols / 2) as isize;
+            let top = (rows / 2) as isize;
+
+            for col in 0..cols {
+                for row in 0..rows {
                     let rval = between.ind_sample(&mut rng);
                     if rval <= p {
-                        board.born_at(col as isize, row as isize);
+                        board.born_at(col as isize - left, row as isize - top);
                     }
                 }
             }
@@ -152,14 +648,85 @@ impl Engine {
 
     pub fn one_iteration(&mut self) {
 
-        let mut next_gen = Self::new_board(self.board_type,
-                                           self.board.get_cols(), self.board.get_rows());
+        if self.detect_periods && self.detector.is_empty() {
+            // remember the starting generation as well
+            self.detector.observe(&self.board, self.iteration);
+        }
+
+        let fast_next_gen = if self.board_type == BoardType::BitPacked {
+            self.board.next_generation(&self.rule)
+        } else {
+            None
+        };
+
+        let cells_checked = match fast_next_gen {
+            Some(next_gen) => {
+                let cells_checked = self.board.get_allocated() as isize;
+
+                // the whole generation is computed at once, so boards are compared
+                if self.rewind.is_enabled() {
+                    self.rewind.push(Delta::new(&self.board.get_cells(), &next_gen));
+                }
+
+                self.board = next_gen;
+                cells_checked
+            },
+            None => self.step_cells()
+        };
+
+        let density = (self.board.get_population() as f64) / (cells_checked as f64);
+
+        let target_type = if density < SPARSE_BOARD_DENSITY {
+            BoardType::Hashed
+        } else if density >= DENSE_BOARD_DENSITY && self.can_use_packed_board() {
+            BoardType::BitPacked
+        } else {
+            BoardType::SymVec
+        };
+
+        if target_type != self.board_type && self.iters_from_prev_switch > SWITCH_BOARD_INERTIA {
+            self.iters_from_prev_switch = 0;
+            self.switch_board_to(target_type);
+        }
+
+        //println!("density {}", density);
+
+        if (self.iteration % ITERATIONS_TO_CLEANUP) == 0 && self.board_type == BoardType::SymVec {
+            // rebuild vector based board once per ITERATIONS_TO_CLEANUP iterations
+            // to improve performance by removing empty cells
+            self.board = self.clone_board(self.board_type);
+        }
+
+        self.iteration += 1;
+        self.iters_from_prev_switch += 1;
+
+        if self.detect_periods {
+            self.detector.observe(&self.board, self.iteration);
+        }
+    }
+
+    fn can_use_packed_board(&self) -> bool {
+        // packed board is useful only if it can compute the whole
+        // generation at once, i.e. for infinite boards and two state totalistic
+        // Life-like rules
+        !self.grid.is_bounded() && !self.rule.is_born(0) && self.rule.get_states() == 2 &&
+            self.rule.is_life_like() && self.rule.is_totalistic()
+    }
+
+    fn step_cells(&mut self) -> isize {
+
+        // compute next generation cell by cell, returns the number
+        // of cells checked to estimate board density
+
+        let mut next_gen = Self::new_board(self.board_type, self.grid);
 
         let mut cells_checked = 0;
 
         let mut density_table: HashMap<isize, MinMax> = HashMap::new();
 
-        for CellDesc { coord, gen, is_alive, .. } in self.board.iter() {
+        let cells: Vec<CellDesc> = self.board.iter().collect();
+
+        for &CellDesc { coord, .. } in cells.iter() {
 
             let col = coord.col;
             let row = coord.row;
@@ -188,32 +755,34 @@ impl Engine {
             } else {
                 cells_checked += 1;
             }
+        }
 
-            // check game rules against current cell
-            let neighbours = self.board.get_vicinity(col, row);
-
-            if is_alive {
-                let neighbours_cnt = neighbours.into_iter().filter(|&x| x).count();
-                // any live cell with fewer than two live neighbours dies,
-                // as if caused by underpopulation.
-
-                // any live cell with more than three live neighbours
-                // dies, as if by overpopulation.
+        let next_cells = if let Some(table) = self.rule.get_table() {
+            // rule tables depend on states of all neighbours
+            table::next_cells(&self.board, table)
+        } else if !self.rule.is_life_like() {
+            // larger neighbourhoods are counted for the whole board at once
+            neighbourhood::next_cells(&self.board, &self.rule)
+        } else if self.threads > 1 && cells.len() >= PARALLEL_MIN_CELLS {
+            self.next_cells_parallel(&cells)
+        } else {
+            self.next_cells(cells.iter())
+        };
 
-                // any live cell with two or three live neighbours
-                // lives on to the next generation.
-                if neighbours_cnt == 2 || neighbours_cnt == 3 {
-                    next_gen.born_at_gen(col, row, gen + 1);
-                }
-            } else {
-                // any dead cell with exactly three live neighbours becomes
-                // a live cell, as if by reproduction.
-                if neighbours.into_iter().filter(|&x| x).count() == 3 {
-                    next_gen.born_at(col, row);
-                }
+        for &(coord, cell) in next_cells.iter() {
+            match cell {
+                Cell::Occupied { gen } => next_gen.born_at_gen(coord.col, coord.row, gen),
+                Cell::Dying { state } => next_gen.set_dying(coord.col, coord.row, state),
+                Cell::State { state } => next_gen.set_state(coord.col, coord.row, state),
+                Cell::Empty => {}
             }
         }
 
+        if self.rewind.is_enabled() {
+            // cells of both generations are already collected
+            self.rewind.push(Delta::from_step(&self.board, &cells, &next_cells, &next_gen));
+        }
+
         self.board = next_gen;
 
         // compute density of hashed board
@@ -228,33 +797,84 @@ impl Engine {
             }
         }
 
-        let density = (self.board.get_population() as f64) / (cells_checked as f64);
+        cells_checked
+    }
 
-        if density < 0.03 && self.board_type == BoardType::SymVec {
-            if self.iters_from_prev_switch > SWITCH_BOARD_INERTIA {
-                self.iters_from_prev_switch = 0;
-                println!("switched to hashed board");
-                self.switch_board();
-            }
-        } else if density >= 0.03 && self.board_type == BoardType::Hashed {
-            if self.iters_from_prev_switch > SWITCH_BOARD_INERTIA {
-                self.iters_from_prev_switch = 0;
-                println!("switched to symvec board");
-                self.switch_board();
+    fn next_cells<'a, I>(&self, cells: I) -> Vec<(Coord, Cell)>
+        where I: Iterator<Item=&'a CellDesc> {
+
+        // returns coordinates and contents of cells that will be alive
+        // or dying in the next generation
+
+        let mut result = Vec::new();
+
+        for &CellDesc { coord, gen, is_alive, state, .. } in cells {
+
+            let cell = if is_alive {
+                Cell::Occupied { gen }
+            } else if state > 1 {
+                Cell::Dying { state }
+            } else {
+                Cell::Empty
+            };
+
+            // dying cells don't depend on their neighbours
+            let vicinity = if state > 1 {
+                Vec::new()
+            } else {
+                self.board.get_vicinity(coord.col, coord.row)
+            };
+
+            // check game rules against current cell
+            match self.rule.next_cell_by_vicinity(cell, &vicinity) {
+                Cell::Empty => {},
+                next => result.push((coord, next)),
             }
         }
 
-        //println!("density {}", density);
+        result
+    }
 
-        if (self.iteration % ITERATIONS_TO_CLEANUP) == 0 && self.board_type == BoardType::SymVec {
-            // rebuild vector based board once per ITERATIONS_TO_CLEANUP iterations
-            // to improve performance by removing empty cells
-            let new_board = self.clone_board(self.board_type);
-            self.set_board(new_board);
+    fn next_cells_parallel(&self, cells: &[CellDesc]) -> Vec<(Coord, Cell)> {
+
+        // split cells into bands of rows and process every band in its own thread,
+        // the board is only read during the computation so no locking is required
+
+        let min_row = cells.iter().map(|x| x.coord.row).min().unwrap_or(0);
+        let max_row = cells.iter().map(|x| x.coord.row).max().unwrap_or(0);
+
+        let band_height = (max_row - min_row) as usize / self.threads + 1;
+
+        let mut bands: Vec<Vec<&CellDesc>> = (0..self.threads).map(|_| Vec::new()).collect();
+
+        for cell in cells {
+            bands[(cell.coord.row - min_row) as usize / band_height].push(cell);
         }
 
-        self.iteration += 1;
-        self.iters_from_prev_switch += 1;
+        thread::scope(|scope| {
+
+            let handles: Vec<_> = bands.iter().map(|band| {
+                scope.spawn(move || self.next_cells(band.iter().cloned()))
+            }).collect();
+
+            // results are merged in order of bands
+            let mut result = Vec::new();
+
+            for handle in handles {
+                result.extend(handle.join().expect("generation stepping thread panicked"));
+            }
+
+            result
+        })
+    }
+
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        // number of threads used to compute next generation, 1 disables parallel mode
+        self.threads = threads.max(1);
     }
 
     pub fn switch_board(&mut self) {
@@ -262,14 +882,67 @@ impl Engine {
         // switch internal board representation hash<->symvec
 
         if self.board_type == BoardType::Hashed {
-            self.board_type = BoardType::SymVec;
+            self.switch_board_to(BoardType::SymVec);
         }  else {
-            self.board_type = BoardType::Hashed;
+            self.switch_board_to(BoardType::Hashed);
         }
+    }
 
-        let new_board = self.clone_board(self.board_type);
+    fn switch_board_to(&mut self, board_type: BoardType) {
 
-        self.set_board(new_board);
+        // the engine doesn't print anything, stdout may carry a saved pattern
+
+        self.board_type = board_type;
+
+        self.board = self.clone_board(self.board_type);
+    }
+
+    pub fn jump(&mut self, generations: u64) -> f64 {
+
+        // advance board by the given number of generations at once using
+        // HashLife algorithm, it works only for infinite boards and two state
+        // totalistic Life-like rules without B0, since empty space must stay
+        // empty, otherwise it falls back to computing generations one by one
+
+        if self.grid.is_bounded() || self.rule.get_states() > 2 || !self.rule.is_life_like() ||
+           !self.rule.is_totalistic() || self.rule.is_born(0) {
+            return self.iterations(generations);
+        }
+
+        let st = time::precise_time_s();
+
+        let coords: Vec<Coord> = self.board.iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+
+        let advanced = {
+            let rule = self.rule.clone();
+            let universe = self.hashlife.get_or_insert_with(|| HashLife::new(rule));
+
+            universe.build_root(&coords).map(|root| {
+                let root = universe.advance(root, generations);
+                universe.to_coords(root)
+            })
+        };
+
+        // cells too far from the origin are computed one by one as well
+        let advanced = match advanced {
+            Some(advanced) => advanced,
+            None => return self.iterations(generations),
+        };
+
+        // generations observed before the jump are not one step apart from
+        // the new one any more, so periods are detected anew, and skipped
+        // generations can't be rewound
+        self.board = self.from_coord_vec(advanced);
+        self.forget_past();
+
+        self.iteration += generations as usize;
+
+        if self.detect_periods {
+            self.detector.observe(&self.board, self.iteration);
+        }
+
+        self.last_iter_time = time::precise_time_s() - st;
+        self.last_iter_time
     }
 
     pub fn iterations(&mut self, n: u64) -> f64 {
@@ -281,4 +954,644 @@ impl Engine {
         self.last_iter_time = time::precise_time_s() - st;
         self.last_iter_time
     }
+
+    pub fn iterations_until_periodic(&mut self, n: u64) -> f64 {
+
+        // same as iterations, but stops as soon as the pattern becomes periodic
+
+        self.set_period_detection(true);
+
+        let st = time::precise_time_s();
+        for _ in 0..n {
+            if self.get_periodicity().is_some() {
+                break;
+            }
+            self.one_iteration();
+        }
+
+        self.last_iter_time = time::precise_time_s() - st;
+        self.last_iter_time
+    }
+}
+
+fn in_region(coord: Coord, top_left: Coord, bottom_right: Coord) -> bool {
+    coord.col >= top_left.col && coord.col <= bottom_right.col &&
+        coord.row >= top_left.row && coord.row <= bottom_right.row
+}
+
+
+#[test]
+fn test_rule_consulted() {
+    // blinker oscillates in Conway's Life
+    let blinker = vec![Coord { col: -1, row: 0 }, Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+
+    let mut life = Engine::new(Some(blinker.clone()), None, None, Rule::default());
+    life.one_iteration();
+
+    assert_eq!(life.get_board().get_population(), 3);
+    assert!(life.get_board().is_alive(0, -1) && life.get_board().is_alive(0, 1));
+
+    // but dies out in Seeds since no cell survives there
+    let mut seeds = Engine::new(Some(blinker), None, None, Rule::parse("B2/S").unwrap());
+    seeds.one_iteration();
+
+    assert!(!seeds.get_board().is_alive(0, 0));
+    assert!(seeds.get_board().is_alive(-1, -1) && seeds.get_board().is_alive(1, 1));
+}
+
+#[test]
+fn test_jump_matches_iterations() {
+    // R-pentomino is chaotic enough to test HashLife against plain iterations
+    let r_pentomino = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 0 }, Coord { col: 0, row: 1 },
+                           Coord { col: 1, row: 1 }, Coord { col: 1, row: 2 }];
+
+    let mut plain = Engine::new(Some(r_pentomino.clone()), None, None, Rule::default());
+    let mut hashlife = Engine::new(Some(r_pentomino), None, None, Rule::default());
+
+    plain.iterations(300);
+    hashlife.jump(100);
+    hashlife.jump(200);
+
+    let mut expected: Vec<Coord> = plain.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+    let mut result: Vec<Coord> = hashlife.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+
+    expected.sort();
+    result.sort();
+
+    assert_eq!(hashlife.cur_iteration(), 300);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_jump_b0_rule() {
+    // empty space doesn't stay empty in B0 rules, so they aren't jumped with HashLife
+    let blinker = vec![Coord { col: -1, row: 0 }, Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+
+    let mut plain = Engine::new(Some(blinker.clone()), None, None, Rule::parse("B03/S23").unwrap());
+    let mut jumped = Engine::new(Some(blinker), None, None, Rule::parse("B03/S23").unwrap());
+
+    plain.iterations(2);
+    jumped.jump(2);
+
+    let mut expected: Vec<Coord> = plain.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+    let mut result: Vec<Coord> = jumped.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+
+    expected.sort();
+    result.sort();
+
+    assert_eq!(jumped.cur_iteration(), 2);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_parallel_matches_single_threaded() {
+    let mut single = Engine::new(None, Some(200), Some(200), Rule::default());
+
+    let soup = single.create_random(0.4);
+    single.set_board(soup);
+
+    let coords: Vec<Coord> = single.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+
+    let mut parallel = Engine::new(Some(coords), Some(200), Some(200), Rule::default());
+    parallel.set_threads(4);
+
+    for _ in 0..10 {
+        single.one_iteration();
+        parallel.one_iteration();
+
+        let mut expected: Vec<(Coord, usize)> = single.get_board().iter().
+            filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+        let mut result: Vec<(Coord, usize)> = parallel.get_board().iter().
+            filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+
+        expected.sort();
+        result.sort();
+
+        assert_eq!(parallel.get_board().get_population(), single.get_board().get_population());
+        assert_eq!(result, expected);
+    }
+}
+
+#[test]
+fn test_packed_matches_hashed() {
+    let mut random = Engine::new(None, Some(100), Some(100), Rule::default());
+    let soup = random.create_random(0.5);
+    random.set_board(soup);
+
+    let coords: Vec<Coord> = random.get_board().iter().filter(|x| x.is_alive).map(|x| x.coord).collect();
+
+    // same soup on infinite boards of different types
+    let mut hashed = Engine::new(Some(coords.clone()), None, None, Rule::default());
+    let mut packed = Engine::new(Some(coords), None, None, Rule::default());
+
+    packed.switch_board_to(BoardType::BitPacked);
+    packed.iters_from_prev_switch = 0;
+
+    for _ in 0..50 {
+        hashed.one_iteration();
+        packed.one_iteration();
+    }
+
+    let mut expected: Vec<(Coord, usize)> = hashed.get_board().iter().
+        filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+    let mut result: Vec<(Coord, usize)> = packed.get_board().iter().
+        filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+
+    expected.sort();
+    result.sort();
+
+    assert!(packed.board_type == BoardType::BitPacked);
+    assert_eq!(packed.get_board().get_population(), hashed.get_board().get_population());
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_switch_to_packed() {
+    // dense field of blocks with default settings
+    let mut coords = Vec::new();
+
+    for i in 0..20 {
+        for j in 0..20 {
+            for &(dc, dr) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter() {
+                coords.push(Coord { col: i * 3 + dc, row: j * 3 + dr });
+            }
+        }
+    }
+
+    let mut engine = Engine::new(Some(coords), None, None, Rule::default());
+
+    for _ in 0..SWITCH_BOARD_INERTIA + 10 {
+        engine.one_iteration();
+    }
+
+    assert!(engine.board_type == BoardType::BitPacked);
+
+    // blocks are still lifes, so all their cells are as old as the pattern
+    let gen = engine.get_board().iter().find(|x| x.is_alive).unwrap().gen;
+
+    assert!(gen > SWITCH_BOARD_INERTIA);
+    assert!(engine.get_board().iter().filter(|x| x.is_alive).all(|x| x.gen == gen));
+    assert_eq!(engine.get_board().get_population(), 1600);
+}
+
+#[test]
+fn test_periodicity() {
+    let blinker = vec![Coord { col: -1, row: 0 }, Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+    let block = vec![Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 },
+                     Coord { col: 0, row: 1 }, Coord { col: 1, row: 1 }];
+    let glider = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                      Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }];
+
+    // periods are detected only on request
+    let mut engine = Engine::new(Some(block.clone()), None, None, Rule::default());
+    engine.iterations(10);
+    assert_eq!(engine.get_periodicity(), None);
+
+    let mut engine = Engine::new(Some(block), None, None, Rule::default());
+    engine.iterations_until_periodic(100);
+    assert_eq!(engine.get_periodicity(), Some(Periodicity::Still));
+    assert_eq!(engine.cur_iteration(), 1);
+
+    let mut engine = Engine::new(Some(blinker), None, None, Rule::default());
+    engine.iterations_until_periodic(100);
+    assert_eq!(engine.get_periodicity(), Some(Periodicity::Oscillator { period: 2 }));
+
+    let mut engine = Engine::new(Some(glider), None, None, Rule::default());
+    engine.iterations_until_periodic(100);
+    assert_eq!(engine.get_periodicity(), Some(Periodicity::Spaceship { period: 4, dx: 1, dy: 1 }));
+    assert_eq!(engine.cur_iteration(), 4);
+
+    // editing the board forgets the result
+    engine.get_board_mut().born_at(100, 100);
+    assert_eq!(engine.get_periodicity(), None);
+}
+
+#[test]
+fn test_periodicity_after_jump() {
+    let blinker = vec![Coord { col: -1, row: 0 }, Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+    let block = vec![Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 },
+                     Coord { col: 0, row: 1 }, Coord { col: 1, row: 1 }];
+
+    // generations before a jump are not compared with the ones after it
+    let mut engine = Engine::new(Some(block), None, None, Rule::default());
+    engine.jump(1024);
+    engine.jump(1024);
+    assert_eq!(engine.get_periodicity(), None);
+
+    engine.iterations_until_periodic(100);
+    assert_eq!(engine.get_periodicity(), Some(Periodicity::Still));
+    assert_eq!(engine.cur_iteration(), 2049);
+
+    let mut engine = Engine::new(Some(blinker), None, None, Rule::default());
+    engine.jump(100);
+    engine.jump(100);
+    assert_eq!(engine.get_periodicity(), None);
+
+    engine.iterations_until_periodic(100);
+    assert_eq!(engine.get_periodicity(), Some(Periodicity::Oscillator { period: 2 }));
+}
+
+#[test]
+fn test_set_grid() {
+    let glider = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                      Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }];
+
+    // glider flies forever on a torus
+    let mut torus = Engine::new(None, None, None, Rule::default());
+    torus.set_grid(Grid::parse("T12,12").unwrap());
+    torus.set_predefined(glider.clone());
+    torus.iterations_until_periodic(200);
+
+    assert_eq!(torus.get_board().get_population(), 5);
+    assert_eq!(torus.get_periodicity(), Some(Periodicity::Spaceship { period: 4, dx: 1, dy: 1 }));
+
+    // but crashes into the corner of a bounded plane and becomes a block
+    let mut plane = Engine::new(Some(glider), None, None, Rule::default());
+    plane.set_grid(Grid::parse("P12,12").unwrap());
+    plane.iterations_until_periodic(200);
+
+    assert_eq!(plane.get_grid().topology, Topology::Plane);
+    assert_eq!(plane.get_board().get_population(), 4);
+    assert_eq!(plane.get_periodicity(), Some(Periodicity::Still));
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut engine = Engine::new(None, None, None, Rule::default());
+
+    // cells drawn inside a group are undone at once
+    engine.begin_edit();
+    engine.set_cell(0, 0, true);
+    engine.set_cell(1, 0, true);
+    engine.end_edit();
+    engine.set_cell(2, 0, true);
+
+    assert!(engine.undo());
+    assert_eq!(engine.get_board().get_population(), 2);
+    assert!(!engine.get_board().is_alive(2, 0));
+
+    assert!(engine.undo());
+    assert_eq!(engine.get_board().get_population(), 0);
+    assert!(!engine.undo());
+
+    assert!(engine.redo());
+    assert!(engine.redo());
+    assert_eq!(engine.get_board().get_population(), 3);
+
+    // clearing is undone together with the iteration counter
+    engine.iterations(1);
+    engine.clear();
+    assert_eq!(engine.get_board().get_population(), 0);
+    assert_eq!(engine.cur_iteration(), 0);
+
+    assert!(engine.undo());
+    assert_eq!(engine.cur_iteration(), 1);
+    assert!(engine.get_board().is_alive(1, -1) && engine.get_board().is_alive(1, 1));
+
+    // a new edit drops reverted ones
+    engine.set_cell(1, 0, false);
+    assert!(!engine.redo());
+    assert_eq!(engine.get_board().get_population(), 2);
+
+    assert!(engine.can_undo() && !engine.can_redo());
+    engine.clear_history();
+    assert!(!engine.can_undo() && !engine.undo());
+}
+
+#[test]
+fn test_metadata_reset() {
+    let pattern = loader::from_string(String::from("#N Blinker\nx = 3, y = 1\n3o!")).unwrap();
+
+    let mut engine = Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&pattern);
+    assert_eq!(engine.get_metadata().name, Some(String::from("Blinker")));
+
+    // a cleared board is not the loaded pattern any more, undo brings its name back
+    engine.clear();
+    assert_eq!(engine.get_metadata(), &loader::Metadata::default());
+    assert!(engine.undo());
+    assert_eq!(engine.get_metadata().name, Some(String::from("Blinker")));
+
+    // the same for a board replaced with a random fill
+    let board = engine.from_pattern(&pattern);
+    engine.replace_board(board);
+    assert_eq!(engine.get_metadata(), &loader::Metadata::default());
+    assert!(engine.undo());
+    assert_eq!(engine.get_metadata().name, Some(String::from("Blinker")));
+}
+
+#[test]
+fn test_step_back() {
+    let r_pentomino = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 0 }, Coord { col: 0, row: 1 },
+                           Coord { col: 1, row: 1 }, Coord { col: 1, row: 2 }];
+
+    let mut engine = Engine::new(Some(r_pentomino.clone()), None, None, Rule::default());
+    engine.set_rewind_capacity(10);
+
+    let mut history: Vec<Vec<(Coord, usize)>> = Vec::new();
+
+    for _ in 0..20 {
+        let mut cells: Vec<(Coord, usize)> = engine.get_board().iter().
+            filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+        cells.sort();
+        history.push(cells);
+
+        engine.one_iteration();
+    }
+
+    // only the last 10 generations are kept
+    for _ in 0..10 {
+        assert!(engine.step_back());
+
+        let mut cells: Vec<(Coord, usize)> = engine.get_board().iter().
+            filter(|x| x.is_alive).map(|x| (x.coord, x.gen)).collect();
+        cells.sort();
+
+        assert_eq!(Some(cells), history.pop());
+        assert_eq!(engine.cur_iteration(), history.len());
+    }
+
+    assert!(!engine.step_back());
+    assert_eq!(engine.cur_iteration(), 10);
+
+    // states of Generations rules and generations computed by the packed board are restored as well
+    for &(rule, packed) in [("B2/S/C3", false), ("B3/S23", true)].iter() {
+        let mut engine = Engine::new(Some(r_pentomino.clone()), None, None, Rule::parse(rule).unwrap());
+        engine.set_rewind_capacity(10);
+
+        if packed {
+            engine.switch_board_to(BoardType::BitPacked);
+            engine.iters_from_prev_switch = 0;
+        }
+
+        let mut history: Vec<Vec<(Coord, usize)>> = Vec::new();
+
+        for _ in 0..10 {
+            let mut cells: Vec<(Coord, usize)> = engine.get_board().iter().
+                filter(|x| x.state != 0).map(|x| (x.coord, x.state)).collect();
+            cells.sort();
+            history.push(cells);
+
+            engine.one_iteration();
+        }
+
+        while let Some(expected) = history.pop() {
+            assert!(engine.step_back());
+
+            let mut cells: Vec<(Coord, usize)> = engine.get_board().iter().
+                filter(|x| x.state != 0).map(|x| (x.coord, x.state)).collect();
+            cells.sort();
+
+            assert_eq!(cells, expected);
+        }
+    }
+}
+
+#[test]
+fn test_generations_rule() {
+    // in Brian's Brain every live cell dies at once passing through
+    // a single refractory state
+    let pair = vec![Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+
+    let mut engine = Engine::new(Some(pair), None, None, Rule::parse("B2/S/C3").unwrap());
+    engine.set_rewind_capacity(4);
+    engine.one_iteration();
+
+    let board = engine.get_board();
+    assert_eq!(board.get_cell_state(0, 0), 2);
+    assert_eq!(board.get_cell_state(1, 0), 2);
+
+    // cells above and below the pair are born
+    assert!(board.is_alive(0, -1) && board.is_alive(1, -1) && board.is_alive(0, 1) && board.is_alive(1, 1));
+    assert_eq!(board.get_population(), 4);
+
+    engine.one_iteration();
+
+    // refractory cells become empty and block births
+    assert_eq!(engine.get_board().get_cell_state(0, 0), 0);
+    assert_eq!(engine.get_board().get_cell_state(0, -1), 2);
+    assert!(!engine.get_board().is_alive(0, 0));
+
+    // stepping back restores dying cells as well
+    assert!(engine.step_back());
+    assert_eq!(engine.get_board().get_cell_state(0, 0), 2);
+    assert_eq!(engine.get_board().get_population(), 4);
+}
+
+#[test]
+fn test_extended_neighbourhoods() {
+    use board::cell_state;
+
+    // every generation is compared with neighbours counted cell by cell
+    // both on the infinite board and on a torus smaller than the neighbourhood
+    let rules = ["R5,C0,M1,S34..58,B34..45,NM", "R3,C3,M0,S4..9,B5..7,NN", "R2,C0,M0,S5..8,B6..9,NC",
+                 "B2/S34H", "B2/S1V"];
+
+    // asymmetric soup of 9x9 cells centered at the origin
+    let soup = ["oo.o..o.o", ".o.oo.oo.", "o..o.o..o", ".ooo...oo", "o.o.oo.o.",
+                "oo...o..o", "..oo.oo.o", "o.o.o..o.", ".oo..ooo."];
+
+    for rule_str in rules.iter() {
+        for &size in [None, Some(9)].iter() {
+            let rule = Rule::parse(rule_str).unwrap();
+
+            let mut engine = Engine::new(None, size, size, rule.clone());
+            engine.set_grid(Grid::new(Topology::default(), size, size));
+
+            for (row, line) in soup.iter().enumerate() {
+                for (col, c) in line.chars().enumerate() {
+                    if c == 'o' {
+                        engine.get_board_mut().born_at(col as isize - 4, row as isize - 4);
+                    }
+                }
+            }
+
+            for _ in 0..5 {
+                let board = engine.get_board();
+                let offsets = rule.get_neighbourhood().get_offsets();
+
+                let mut expected: Vec<(Coord, usize)> = Vec::new();
+                for col in -20..21 {
+                    for row in -20..21 {
+                        if board.constrain_board(col, row) != Some((col, row)) {
+                            continue;
+                        }
+                        let count = offsets.iter().filter(|&&(dx, dy, _)| board.is_alive(col + dx, row + dy)).count();
+                        let state = rule.next_cell(board.get_cell(col, row), count);
+                        if state != Cell::Empty {
+                            expected.push((Coord { col, row }, cell_state(&state)));
+                        }
+                    }
+                }
+
+                engine.one_iteration();
+
+                let mut actual: Vec<(Coord, usize)> = engine.get_board().get_cells().into_iter().
+                    map(|(coord, cell)| (coord, cell_state(&cell))).collect();
+                actual.sort();
+
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn sorted_coords<I: IntoIterator<Item=Coord>>(coords: I) -> Vec<Coord> {
+    // cells in the order of rows, so tests compare them with the expected ones
+    let mut coords: Vec<Coord> = coords.into_iter().collect();
+    coords.sort_by_key(|c| (c.row, c.col));
+    coords
+}
+
+#[test]
+fn test_wireworld() {
+    // electron moves along a wire from left to right
+    let pattern = loader::from_string(String::from("x = 6, y = 1, rule = WireWorld\nBA4C!")).unwrap();
+
+    let mut engine = Engine::new(None, None, None, Rule::default());
+    engine.load_pattern(&pattern);
+
+    assert_eq!(engine.get_board().get_population(), 6);
+
+    engine.one_iteration();
+
+    let states: Vec<usize> = (0..6).map(|col| engine.get_board().get_cell_state(col, 0)).collect();
+    assert_eq!(states, vec![3, 2, 1, 3, 3, 3]);
+
+    engine.iterations(3);
+
+    let states: Vec<usize> = (0..6).map(|col| engine.get_board().get_cell_state(col, 0)).collect();
+    assert_eq!(states, vec![3, 3, 3, 3, 2, 1]);
+
+    // the electron leaves the wire
+    engine.iterations(2);
+    assert_eq!(engine.get_board().get_population(), 6);
+    assert!((0..6).all(|col| engine.get_board().get_cell_state(col, 0) == 3));
+}
+
+#[test]
+fn test_hensel_rule() {
+    // in B2-a/S12 two adjacent cells give no birth, while in B2/S12 they do
+    let pair = vec![Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }];
+
+    let mut engine = Engine::new(Some(pair.clone()), None, None, Rule::parse("B2-a/S12").unwrap());
+    engine.one_iteration();
+    assert_eq!(engine.get_board().get_population(), 2);
+
+    let mut engine = Engine::new(Some(pair), None, None, Rule::parse("B2/S12").unwrap());
+    engine.one_iteration();
+    assert_eq!(engine.get_board().get_population(), 6);
+}
+
+#[test]
+fn test_stamp() {
+    use board::transform::Transform;
+
+    let cells = |engine: &Engine| sorted_coords(engine.get_board().get_cells().into_iter().map(|(coord, _)| coord));
+
+    // block of 2x2 cells at the origin and a 3x1 blinker pattern with a dead cell at its end
+    let block = vec![Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 },
+                     Coord { col: 0, row: 1 }, Coord { col: 1, row: 1 }];
+    let mut blinker = loader::from_string(String::from("x = 4, y = 1\n3o!")).unwrap();
+
+    let mut engine = Engine::new(Some(block.clone()), None, None, Rule::default());
+    engine.stamp(&blinker, Coord { col: 1, row: 1 }, StampMode::Or);
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 0, row: 1 },
+                                Coord { col: 1, row: 1 }, Coord { col: 2, row: 1 }, Coord { col: 3, row: 1 }]);
+
+    // stamp is undone at once
+    assert!(engine.undo());
+    assert_eq!(cells(&engine), block);
+
+    engine.stamp(&blinker, Coord { col: 1, row: 1 }, StampMode::Xor);
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 0, row: 1 },
+                                Coord { col: 2, row: 1 }, Coord { col: 3, row: 1 }]);
+
+    let mut engine = Engine::new(Some(block.clone()), None, None, Rule::default());
+    engine.stamp(&blinker, Coord { col: -1, row: 1 }, StampMode::And);
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 },
+                                Coord { col: 0, row: 1 }, Coord { col: 1, row: 1 }]);
+
+    // dead cell at the end of the pattern clears the board as well
+    engine.stamp(&blinker, Coord { col: -2, row: 1 }, StampMode::And);
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 0 }, Coord { col: 1, row: 0 }, Coord { col: 0, row: 1 }]);
+
+    // cells outside the rectangle of the pattern stay
+    engine.stamp(&blinker, Coord { col: -3, row: 0 }, StampMode::Overwrite);
+    assert_eq!(cells(&engine), [Coord { col: -3, row: 0 }, Coord { col: -2, row: 0 }, Coord { col: -1, row: 0 },
+                                Coord { col: 1, row: 0 }, Coord { col: 0, row: 1 }]);
+
+    // rotated pattern is vertical, its offset is not used by stamp
+    blinker.transform(Transform::Rotate90);
+    blinker.translate(100, 100);
+    assert_eq!((blinker.width, blinker.height), (1, 4));
+
+    let mut engine = Engine::new(None, None, None, Rule::default());
+    engine.stamp(&blinker, Coord { col: 5, row: -5 }, StampMode::Or);
+    assert_eq!(cells(&engine), [Coord { col: 5, row: -5 }, Coord { col: 5, row: -4 }, Coord { col: 5, row: -3 }]);
+
+    // loaded pattern is placed at its offset
+    engine.load_pattern(&blinker);
+    assert_eq!(cells(&engine), [Coord { col: 100, row: 100 }, Coord { col: 100, row: 101 }, Coord { col: 100, row: 102 }]);
+
+    // states of multi-state patterns are kept
+    let mut engine = Engine::new(None, None, None, Rule::parse("WireWorld").unwrap());
+    engine.stamp(&loader::from_string(String::from("x = 3, y = 1\nBAC!")).unwrap(), Coord { col: 0, row: 0 }, StampMode::Or);
+    assert_eq!((0..3).map(|col| engine.get_board().get_cell_state(col, 0)).collect::<Vec<usize>>(), vec![2, 1, 3]);
+}
+
+#[test]
+fn test_region_edits() {
+    let cells = |engine: &Engine| sorted_coords(engine.get_board().get_cells().into_iter().map(|(coord, _)| coord));
+
+    // glider and a distant cell
+    let pattern = vec![Coord { col: 1, row: 0 }, Coord { col: 2, row: 1 }, Coord { col: 0, row: 2 },
+                       Coord { col: 1, row: 2 }, Coord { col: 2, row: 2 }, Coord { col: 10, row: 10 }];
+
+    let top_left = Coord { col: 0, row: 0 };
+    let bottom_right = Coord { col: 2, row: 2 };
+
+    let mut engine = Engine::new(Some(pattern.clone()), None, None, Rule::default());
+    engine.clear_region(top_left, bottom_right, true);
+    assert_eq!(cells(&engine), [Coord { col: 10, row: 10 }]);
+
+    assert!(engine.undo());
+    engine.clear_region(top_left, bottom_right, false);
+    assert_eq!(cells(&engine), &pattern[..5]);
+
+    // glider is rotated within its square
+    engine.transform_region(top_left, bottom_right, Transform::Rotate90);
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 0 }, Coord { col: 0, row: 1 }, Coord { col: 2, row: 1 },
+                                Coord { col: 0, row: 2 }, Coord { col: 1, row: 2 }]);
+
+    // region of a single row becomes a column, the cell it covers is cleared
+    let mut engine = Engine::new(Some(pattern.clone()), None, None, Rule::default());
+    assert_eq!(engine.transform_region(top_left, Coord { col: 2, row: 0 }, Transform::Rotate90),
+               (top_left, Coord { col: 0, row: 2 }));
+    assert_eq!(cells(&engine), [Coord { col: 0, row: 1 }, Coord { col: 2, row: 1 }, Coord { col: 1, row: 2 },
+                                Coord { col: 2, row: 2 }, Coord { col: 10, row: 10 }]);
+
+    // transform is undone at once
+    assert!(engine.undo());
+    assert_eq!(cells(&engine), pattern);
+
+    engine.transform_region(top_left, bottom_right, Transform::FlipVertical);
+    engine.transform_region(top_left, bottom_right, Transform::FlipVertical);
+    assert_eq!(cells(&engine), pattern);
+
+    // random fill replaces cells of the region
+    engine.fill_random(top_left, bottom_right, 1.0);
+    assert_eq!(engine.get_board().get_population(), 10);
+
+    engine.fill_random(top_left, bottom_right, 0.0);
+    assert_eq!(cells(&engine), [Coord { col: 10, row: 10 }]);
+
+    // cells beyond edges of a bounded board are filled only once
+    let mut engine = Engine::new(None, Some(4), Some(4), Rule::default());
+    engine.fill_random(Coord { col: -10, row: -10 }, Coord { col: 10, row: 10 }, 1.0);
+    assert_eq!(engine.get_board().get_population(), 16);
+
+    assert!(engine.undo());
+    assert_eq!(engine.get_board().get_population(), 0);
 }
@@ -0,0 +1,395 @@
+//! Neighbourhoods of extended range rules.
+//!
+//! Larger than Life rules count live cells in a range-r neighbourhood of
+//! a cell instead of its 8 nearest neighbours. Supported shapes are Moore
+//! (a square), von Neumann (a diamond), circular, hexagonal and custom
+//! weights given for every cell of the (2r+1)x(2r+1) square.
+//!
+//! Hexagonal grid is emulated the same way as in Golly: rows are skewed,
+//! so the north-east and south-west cells are not neighbours and the
+//! remaining 6 cells of the square form a hexagon.
+//!
+//! Every shape except the weighted one covers a continuous span of cells
+//! in each row, so neighbours are counted with prefix sums over rows of
+//! the board, which takes O(r) operations per cell.
+
+use std::collections::{HashMap, HashSet};
+
+use board::{Board, Cell, Coord};
+use core::Rule;
+
+// larger ranges are rejected by the rule parser
+pub const MAX_RANGE: usize = 500;
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Shape {
+    Moore,
+    VonNeumann,
+    Circular,
+    Hexagonal,
+    // weights of all cells of the square row by row, (2r+1)^2 values
+    Weighted(Vec<usize>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Neighbourhood {
+    pub shape: Shape,
+    pub range: usize,
+    // the cell is counted as its own neighbour, weighted
+    // neighbourhoods define the weight of the cell itself
+    pub include_center: bool,
+}
+
+impl Default for Neighbourhood {
+
+    fn default() -> Self {
+        // 8 nearest neighbours of Life-like rules
+        Neighbourhood::new(Shape::Moore, 1, false)
+    }
+
+}
+
+impl Neighbourhood {
+
+    pub fn new(shape: Shape, range: usize, include_center: bool) -> Self {
+        Neighbourhood {
+            shape,
+            range,
+            include_center,
+        }
+    }
+
+    #[inline]
+    pub fn is_moore(&self) -> bool {
+        // true for the neighbourhood of Life-like rules
+        *self == Neighbourhood::default()
+    }
+
+    pub fn row_span(&self, dy: isize) -> Option<(isize, isize)> {
+
+        // first and last columns of the neighbourhood in the given row relative
+        // to the cell, None if the row is empty or the shape is weighted
+
+        let r = self.range as isize;
+
+        if dy.abs() > r {
+            return None;
+        }
+
+        let half_width = match self.shape {
+            Shape::Moore => r,
+            Shape::VonNeumann => r - dy.abs(),
+            Shape::Circular => {
+                // cells closer than r + 1/2 to the center, i.e. x^2 + y^2 <= r^2 + r
+                let limit = r * r + r - dy * dy;
+                let mut x = (limit as f64).sqrt() as isize;
+                while x * x > limit {
+                    x -= 1;
+                }
+                while (x + 1) * (x + 1) <= limit {
+                    x += 1;
+                }
+                x
+            },
+            // rows above the cell are shifted to the right
+            // and rows below it to the left
+            Shape::Hexagonal => return Some(if dy < 0 { (-r, r + dy) } else { (-r + dy, r) }),
+            Shape::Weighted(_) => return None,
+        };
+
+        Some((-half_width, half_width))
+    }
+
+    pub fn weight(&self, dx: isize, dy: isize) -> usize {
+
+        let r = self.range as isize;
+
+        if dx.abs() > r || dy.abs() > r {
+            return 0;
+        }
+
+        if let Shape::Weighted(ref weights) = self.shape {
+            return weights[((dy + r) * (2 * r + 1) + dx + r) as usize];
+        }
+
+        if dx == 0 && dy == 0 {
+            return if self.include_center { 1 } else { 0 };
+        }
+
+        match self.row_span(dy) {
+            Some((first, last)) if dx >= first && dx <= last => 1,
+            _ => 0
+        }
+    }
+
+    pub fn get_offsets(&self) -> Vec<(isize, isize, usize)> {
+
+        // all cells of the neighbourhood with their weights
+
+        let r = self.range as isize;
+        let mut result = Vec::new();
+
+        for dy in -r..r + 1 {
+            for dx in -r..r + 1 {
+                let weight = self.weight(dx, dy);
+                if weight > 0 {
+                    result.push((dx, dy, weight));
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn max_count(&self) -> usize {
+        // count of a cell surrounded by live cells only
+        self.get_offsets().iter().map(|&(_, _, weight)| weight).sum()
+    }
+
+}
+
+fn count_dense(board: &Board, neighbourhood: &Neighbourhood, non_empty: &HashSet<Coord>,
+               top_left: Coord, bottom_right: Coord) -> Vec<(Coord, usize)> {
+
+    // counts neighbours of every cell inside the rectangle using
+    // a copy of the rectangle extended by the range in all directions
+
+    let r = neighbourhood.range as isize;
+
+    let width = (bottom_right.col - top_left.col + 1 + 2 * r) as usize;
+    let height = (bottom_right.row - top_left.row + 1 + 2 * r) as usize;
+
+    let mut alive = vec![0usize; width * height];
+
+    if board.get_grid().is_bounded() {
+        // cells beyond edges are glued to the cells of the board
+        for y in 0..height {
+            for x in 0..width {
+                if board.is_alive(top_left.col - r + x as isize, top_left.row - r + y as isize) {
+                    alive[y * width + x] = 1;
+                }
+            }
+        }
+    } else {
+        for coord in non_empty.iter() {
+            if board.is_alive(coord.col, coord.row) {
+                let x = (coord.col - top_left.col + r) as usize;
+                let y = (coord.row - top_left.row + r) as usize;
+                alive[y * width + x] = 1;
+            }
+        }
+    }
+
+    // prefix[y * (width + 1) + x] is the number of live cells in row y left of x
+    let mut prefix = vec![0usize; (width + 1) * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            prefix[y * (width + 1) + x + 1] = prefix[y * (width + 1) + x] + alive[y * width + x];
+        }
+    }
+
+    let spans: Option<Vec<(isize, isize, isize)>> = (-r..r + 1).
+        map(|dy| neighbourhood.row_span(dy).map(|(first, last)| (dy, first, last))).collect();
+
+    let offsets = neighbourhood.get_offsets();
+
+    let mut result = Vec::new();
+
+    for y in r..height as isize - r {
+        for x in r..width as isize - r {
+
+            let count = match spans {
+                Some(ref spans) => {
+                    let mut count = 0;
+                    for &(dy, first, last) in spans.iter() {
+                        let row_start = ((y + dy) * (width as isize + 1)) as usize;
+                        count += prefix[row_start + (x + last + 1) as usize] -
+                                 prefix[row_start + (x + first) as usize];
+                    }
+                    if !neighbourhood.include_center {
+                        count -= alive[y as usize * width + x as usize];
+                    }
+                    count
+                },
+                None => offsets.iter().map(|&(dx, dy, weight)|
+                                           alive[(y + dy) as usize * width + (x + dx) as usize] * weight).sum()
+            };
+
+            let coord = Coord { col: top_left.col + x - r, row: top_left.row + y - r };
+
+            if count > 0 || non_empty.contains(&coord) {
+                result.push((coord, count));
+            }
+        }
+    }
+
+    result
+}
+
+fn count_sparse(board: &Board, neighbourhood: &Neighbourhood, non_empty: &HashSet<Coord>) -> Vec<(Coord, usize)> {
+
+    // adds the weight of every live cell to all cells that have it in their
+    // neighbourhood, i.e. the live cell lies at (dx, dy) from them
+
+    let offsets = neighbourhood.get_offsets();
+
+    let mut counts: HashMap<Coord, usize> = HashMap::new();
+
+    for coord in non_empty.iter() {
+
+        if !board.is_alive(coord.col, coord.row) {
+            continue;
+        }
+
+        for &(dx, dy, weight) in offsets.iter() {
+            if let Some((col, row)) = board.constrain_board(coord.col - dx, coord.row - dy) {
+                *counts.entry(Coord { col, row }).or_insert(0) += weight;
+            }
+        }
+    }
+
+    // cells without live neighbours may still change their state
+    for coord in non_empty.iter() {
+        counts.entry(*coord).or_insert(0);
+    }
+
+    counts.into_iter().collect()
+}
+
+pub fn count_neighbours(board: &Board, neighbourhood: &Neighbourhood) -> Vec<(Coord, usize)> {
+
+    // returns weighted counts of live neighbours of all cells that can change
+    // in the next generation, i.e. non empty cells and cells near live ones
+
+    let non_empty: HashSet<Coord> = board.get_cells().into_iter().map(|(coord, _)| coord).collect();
+
+    if non_empty.is_empty() {
+        return Vec::new();
+    }
+
+    let r = neighbourhood.range as isize;
+    let grid = board.get_grid();
+
+    let mut top_left = Coord {
+        col: non_empty.iter().map(|c| c.col).min().unwrap() - r,
+        row: non_empty.iter().map(|c| c.row).min().unwrap() - r,
+    };
+
+    let mut bottom_right = Coord {
+        col: non_empty.iter().map(|c| c.col).max().unwrap() + r,
+        row: non_empty.iter().map(|c| c.row).max().unwrap() + r,
+    };
+
+    // if neighbourhoods cross an edge of a bounded board, cells on the opposite
+    // side of a wrapped board may change as well, so all cells of bounded
+    // directions are counted
+    let crosses = |range: Option<(isize, isize)>, min: isize, max: isize| {
+        match range {
+            Some((first, last)) => min < first || max > last,
+            None => false,
+        }
+    };
+
+    if crosses(grid.col_range(), top_left.col, bottom_right.col) ||
+        crosses(grid.row_range(), top_left.row, bottom_right.row) {
+
+        if let Some((first, last)) = grid.col_range() {
+            top_left.col = first;
+            bottom_right.col = last;
+        }
+
+        if let Some((first, last)) = grid.row_range() {
+            top_left.row = first;
+            bottom_right.row = last;
+        }
+    }
+
+    // prefix sums need O(r) operations per cell of the rectangle, while
+    // spreading weights of live cells takes the neighbourhood size per live cell
+    let live = non_empty.iter().filter(|c| board.is_alive(c.col, c.row)).count();
+
+    let area = ((bottom_right.col - top_left.col + 1) * (bottom_right.row - top_left.row + 1)) as usize;
+
+    let cost_per_cell = match neighbourhood.shape {
+        Shape::Weighted(_) => neighbourhood.get_offsets().len(),
+        _ => 2 * neighbourhood.range + 1,
+    };
+
+    // topologies with twisted edges are not symmetric for spreading,
+    // but they are always bounded, so the rectangle is never too large
+    let fully_bounded = grid.cols.is_some() && grid.rows.is_some();
+
+    if fully_bounded || area * cost_per_cell <= live * neighbourhood.get_offsets().len() {
+        count_dense(board, neighbourhood, &non_empty, top_left, bottom_right)
+    } else {
+        count_sparse(board, neighbourhood, &non_empty)
+    }
+}
+
+pub fn next_cells(board: &Board, rule: &Rule) -> Vec<(Coord, Cell)> {
+
+    // returns coordinates and contents of cells that will be alive
+    // or dying in the next generation of an extended range rule
+
+    count_neighbours(board, rule.get_neighbourhood()).into_iter().
+        map(|(coord, count)| (coord, rule.next_cell(board.get_cell(coord.col, coord.row), count))).
+        filter(|&(_, cell)| cell != Cell::Empty).
+        collect()
+}
+
+
+#[test]
+fn test_row_span() {
+    let moore = Neighbourhood::new(Shape::Moore, 2, false);
+    assert_eq!(moore.row_span(-2), Some((-2, 2)));
+    assert_eq!(moore.row_span(3), None);
+    assert_eq!(moore.max_count(), 24);
+
+    let von_neumann = Neighbourhood::new(Shape::VonNeumann, 2, true);
+    assert_eq!(von_neumann.row_span(1), Some((-1, 1)));
+    assert_eq!(von_neumann.max_count(), 13);
+
+    // radius 2.5 circle
+    let circular = Neighbourhood::new(Shape::Circular, 2, false);
+    assert_eq!(circular.row_span(0), Some((-2, 2)));
+    assert_eq!(circular.row_span(1), Some((-2, 2)));
+    assert_eq!(circular.row_span(2), Some((-1, 1)));
+    assert_eq!(circular.max_count(), 20);
+
+    // north-east and south-west cells are excluded
+    let hexagonal = Neighbourhood::new(Shape::Hexagonal, 1, false);
+    assert_eq!(hexagonal.row_span(-1), Some((-1, 0)));
+    assert_eq!(hexagonal.row_span(1), Some((0, 1)));
+    assert_eq!(hexagonal.max_count(), 6);
+    assert_eq!(Neighbourhood::new(Shape::Hexagonal, 2, true).max_count(), 19);
+}
+
+#[test]
+fn test_count_dense_matches_sparse() {
+    use board::HashedBoard;
+
+    let mut board = Board::new(HashedBoard::new(), None, None);
+
+    for &(col, row) in [(0, 0), (1, 0), (3, 2), (-2, 4), (0, 5)].iter() {
+        board.born_at(col, row);
+    }
+
+    let shapes = vec![Shape::Moore, Shape::VonNeumann, Shape::Circular, Shape::Hexagonal,
+                      Shape::Weighted((0..25).map(|x| x % 3).collect())];
+
+    for shape in shapes {
+        let neighbourhood = Neighbourhood::new(shape, 2, true);
+        let non_empty: HashSet<Coord> = board.get_cells().into_iter().map(|(coord, _)| coord).collect();
+
+        let mut dense = count_dense(&board, &neighbourhood, &non_empty,
+                                    Coord { col: -4, row: -2 }, Coord { col: 5, row: 7 });
+        let mut sparse = count_sparse(&board, &neighbourhood, &non_empty);
+
+        dense.sort();
+        sparse.sort();
+
+        assert_eq!(dense, sparse);
+    }
+}
@@ -0,0 +1,142 @@
+//! Ring buffer of recently computed generations.
+//!
+//! Every generation is stored as a delta from the previous one: previous
+//! contents of cells that were born, died or moved to the next state of a
+//! Generations rule. Cells that survived are only one generation older, so
+//! they are not stored at all.
+
+use std::collections::{HashMap, VecDeque};
+
+use board::{Board, Cell, CellDesc, Coord};
+
+
+#[inline]
+fn same_state(a: &Cell, b: &Cell) -> bool {
+    // generations of live cells are not compared
+    match (*a, *b) {
+        (Cell::Empty, Cell::Empty) => true,
+        (Cell::Occupied { .. }, Cell::Occupied { .. }) => true,
+        (Cell::Dying { state: a }, Cell::Dying { state: b }) => a == b,
+        (Cell::State { state: a }, Cell::State { state: b }) => a == b,
+        _ => false
+    }
+}
+
+pub struct Delta {
+    // previous contents of cells that were born, died or changed their state
+    changed: Vec<(Coord, Cell)>,
+}
+
+impl Delta {
+
+    pub fn new(prev_cells: &[(Coord, Cell)], board: &Board) -> Self {
+
+        // difference between cells of the previous generation and the board
+
+        let prev: HashMap<Coord, Cell> = prev_cells.iter().cloned().collect();
+
+        let mut changed: Vec<(Coord, Cell)> = prev_cells.iter().
+            filter(|&&(coord, cell)| !same_state(&cell, &board.get_cell(coord.col, coord.row))).
+            cloned().collect();
+
+        changed.extend(board.get_cells().into_iter().
+                       filter(|&(coord, _)| !prev.contains_key(&coord)).
+                       map(|(coord, _)| (coord, Cell::Empty)));
+
+        Delta { changed }
+    }
+
+    pub fn from_step(board: &Board, cells: &[CellDesc], next_cells: &[(Coord, Cell)], next_board: &Board) -> Self {
+
+        // same as new, but uses cells of the board and non empty cells of
+        // the next generation which were already collected to compute it
+
+        let mut changed: Vec<(Coord, Cell)> = cells.iter().
+            filter(|desc| desc.state != 0 && next_board.get_cell(desc.coord.col, desc.coord.row) == Cell::Empty).
+            map(|desc| (desc.coord, board.get_cell(desc.coord.col, desc.coord.row))).collect();
+
+        for &(coord, next) in next_cells {
+            let prev = board.get_cell(coord.col, coord.row);
+            if !same_state(&prev, &next) {
+                changed.push((coord, prev));
+            }
+        }
+
+        Delta { changed }
+    }
+
+    pub fn revert(&self, board: &Board) -> Vec<(Coord, Cell)> {
+
+        // returns cells of the previous generation
+
+        let changed: HashMap<Coord, Cell> = self.changed.iter().cloned().collect();
+
+        let mut cells: Vec<(Coord, Cell)> = board.get_cells().into_iter().
+            filter(|&(coord, _)| !changed.contains_key(&coord)).
+            map(|(coord, cell)| match cell {
+                // survived cells are one generation younger
+                Cell::Occupied { gen } => (coord, Cell::Occupied { gen: if gen > 1 { gen - 1 } else { 1 } }),
+                _ => (coord, cell)
+            }).collect();
+
+        cells.extend(self.changed.iter().filter(|&&(_, cell)| cell != Cell::Empty).cloned());
+
+        cells
+    }
+
+}
+
+pub struct Rewind {
+    deltas: VecDeque<Delta>,
+    capacity: usize,
+}
+
+impl Rewind {
+
+    pub fn new(capacity: usize) -> Self {
+        Rewind {
+            deltas: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.deltas.len() > capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn push(&mut self, delta: Delta) {
+
+        if !self.is_enabled() {
+            return;
+        }
+
+        // the oldest generation is forgotten when the buffer is full
+        if self.deltas.len() == self.capacity {
+            self.deltas.pop_front();
+        }
+
+        self.deltas.push_back(delta);
+    }
+
+    pub fn pop(&mut self) -> Option<Delta> {
+        self.deltas.pop_back()
+    }
+
+}
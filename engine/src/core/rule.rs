@@ -0,0 +1,788 @@
+//! Life-like cellular automaton rules.
+//!
+//! A rule says for how many live neighbours a dead cell becomes alive (birth)
+//! and for how many live neighbours a live cell stays alive (survival).
+//! Rules are usually written in B/S notation, e.g. Conway's Life is "B3/S23",
+//! HighLife is "B36/S23" and Seeds is "B2/S". Several other spellings are
+//! accepted by the parser:
+//! ```
+//! # use engine::Rule;
+//! let life = Rule::parse("B3/S23").unwrap();
+//! assert_eq!(Rule::parse("b3s23").unwrap(), life);
+//! assert_eq!(Rule::parse("23/3").unwrap(), life);
+//! ```
+//!
+//! Generations rules add the number of cell states, a live cell that doesn't
+//! survive passes through states 2, 3, ... before it becomes empty, e.g.
+//! Brian's Brain is "B2/S/C3" or "/2/3" and Star Wars is "345/2/4".
+//!
+//! Isotropic non-totalistic rules add letters of Hensel notation to the
+//! counts to choose which neighbours are alive, e.g. "B2-a/S12" or tlife
+//! "B3/S2-i34q".
+//!
+//! Golly suffixes select other neighbourhoods of the nearest cells, "H" is
+//! hexagonal, e.g. "B2/S34H", and "V" is von Neumann, e.g. "B2/S1V".
+//!
+//! Larger than Life rules count live cells in a larger neighbourhood and
+//! are written in HROT notation, e.g. Bosco's Rule is
+//! "R5,C0,M1,S34..58,B34..45,NM": range 5, 2 states, the cell itself is
+//! counted, survival and birth count ranges and the Moore neighbourhood.
+//! Other neighbourhoods are von Neumann "NN", circular "NC", hexagonal "NH" and custom
+//! weights "NW" followed by a hex digit for every cell of the square.
+//!
+//! Rule tables in Golly's `.rule` format give transitions of cells with
+//! any number of states, they are created with `Rule::from_table`, built-in
+//! tables are parsed by their names, e.g. "WireWorld".
+
+use std::error;
+use std::fmt;
+use std::fmt::{Formatter, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use board::Cell;
+use core::neighbourhood::{Neighbourhood, Shape, MAX_RANGE};
+use core::hensel;
+use core::table::RuleTable;
+
+// maximum number of live neighbours a cell can have
+const MAX_NEIGHBOURS: usize = 8;
+
+
+#[derive(Debug, PartialEq)]
+pub enum RuleError {
+    WrongFormat(String),
+    WrongNeighboursCount(char),
+    // line of a rule table and the reason
+    WrongTable(usize, String),
+}
+
+impl Display for RuleError {
+
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+
+        match *self {
+            RuleError::WrongFormat(ref unparsed) => write!(f, "Wrong rule format {}", unparsed),
+            RuleError::WrongNeighboursCount(c) => write!(f, "Wrong neighbours count {}", c),
+            RuleError::WrongTable(line, ref reason) => write!(f, "Wrong rule table at line {}: {}", line, reason),
+        }
+
+    }
+
+}
+
+impl error::Error for RuleError {
+
+    fn description(&self) -> &str {
+
+        match *self {
+            RuleError::WrongFormat(_) => "Wrong rule format",
+            RuleError::WrongNeighboursCount(_) => "Wrong neighbours count",
+            RuleError::WrongTable(..) => "Wrong rule table",
+        }
+
+    }
+
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    // indexed by the number of live neighbours, for isotropic
+    // non-totalistic rules true if any configuration matches
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    // number of cell states including the empty one, 2 for Life-like rules
+    states: usize,
+    neighbourhood: Neighbourhood,
+    // birth and survival indexed by the mask of live neighbours in the order
+    // of Board::get_vicinity, only for isotropic non-totalistic rules
+    masks: Option<(Vec<bool>, Vec<bool>)>,
+    // transitions of all states, birth and survival are not used
+    table: Option<Arc<RuleTable>>,
+}
+
+impl Default for Rule {
+
+    fn default() -> Self {
+        // Conway's Game of Life
+        Rule::new(&[3], &[2, 3])
+    }
+
+}
+
+impl Rule {
+
+    pub fn new(birth: &[usize], survival: &[usize]) -> Self {
+        Rule::generations(birth, survival, 2)
+    }
+
+    pub fn generations(birth: &[usize], survival: &[usize], states: usize) -> Self {
+        // rule with dying cells, states must be at least 2
+        Rule::extended(Neighbourhood::default(), birth, survival, states)
+    }
+
+    pub fn extended(neighbourhood: Neighbourhood, birth: &[usize], survival: &[usize],
+                    states: usize) -> Self {
+
+        // rule with an arbitrary neighbourhood, counts must not
+        // exceed the maximum count of the neighbourhood
+
+        let max_count = neighbourhood.max_count();
+
+        let mut rule = Rule {
+            birth: vec![false; max_count + 1],
+            survival: vec![false; max_count + 1],
+            states: states.max(2),
+            neighbourhood,
+            masks: None,
+            table: None,
+        };
+
+        for &n in birth {
+            rule.birth[n] = true;
+        }
+
+        for &n in survival {
+            rule.survival[n] = true;
+        }
+
+        rule
+    }
+
+    fn isotropic(birth_masks: Vec<bool>, survival_masks: Vec<bool>, states: usize) -> Self {
+
+        // rule of the 8 nearest neighbours given by tables of neighbour masks
+
+        let counts = |masks: &[bool]| -> Vec<usize> {
+            (0..MAX_NEIGHBOURS + 1).filter(|&n| (0..hensel::MASKS).
+                any(|mask| masks[mask] && mask.count_ones() as usize == n)).collect()
+        };
+
+        let mut rule = Rule::generations(&counts(&birth_masks), &counts(&survival_masks), states);
+
+        if !hensel::is_totalistic(&birth_masks) || !hensel::is_totalistic(&survival_masks) {
+            rule.masks = Some((birth_masks, survival_masks));
+        }
+
+        rule
+    }
+
+    pub fn from_table(table: RuleTable) -> Self {
+
+        let shape = table.get_shape().clone();
+
+        let mut rule = Rule::extended(Neighbourhood::new(shape, 1, false), &[], &[], table.get_states());
+        rule.table = Some(Arc::new(table));
+
+        rule
+    }
+
+    pub fn parse(rule_str: &str) -> Result<Self, RuleError> {
+
+        let trimmed = rule_str.trim();
+
+        if trimmed.is_empty() {
+            return Err(RuleError::WrongFormat(String::from(rule_str)));
+        }
+
+        if let Some(table) = RuleTable::builtin(trimmed) {
+            return Ok(Rule::from_table(table));
+        }
+
+        if trimmed.starts_with('R') || trimmed.starts_with('r') {
+            return parse_extended(rule_str, &trimmed.to_uppercase());
+        }
+
+        // neighbourhood suffix, e.g. "B2/S34H"
+        let (trimmed, shape) = match trimmed.chars().last() {
+            Some('H') | Some('h') => (&trimmed[..trimmed.len() - 1], Some(Shape::Hexagonal)),
+            Some('V') | Some('v') => (&trimmed[..trimmed.len() - 1], Some(Shape::VonNeumann)),
+            _ => (trimmed, None)
+        };
+
+        let mut birth_part: Option<String> = None;
+        let mut survival_part: Option<String> = None;
+        let mut states_part: Option<String> = None;
+
+        if trimmed.starts_with(['B', 'b', 'S', 's']) {
+
+            // "B36/S23", "b3s23" or "B2/S/C3" notation, all parts are labeled
+            // so they may come in any order and the slash is optional
+
+            let parts = match split_labeled(trimmed) {
+                Some(parts) => parts,
+                None => return Err(RuleError::WrongFormat(String::from(rule_str))),
+            };
+
+            if trimmed.matches('/').count() + 1 > parts.len() {
+                // something like "B3//S23"
+                return Err(RuleError::WrongFormat(String::from(rule_str)));
+            }
+
+            for (label, counts) in parts {
+
+                let target = match label {
+                    'B' => &mut birth_part,
+                    'S' => &mut survival_part,
+                    _ => &mut states_part,
+                };
+
+                if target.is_some() {
+                    return Err(RuleError::WrongFormat(String::from(rule_str)));
+                }
+
+                *target = Some(counts);
+            }
+
+        } else {
+
+            // "23/36" or "345/2/4" notation: survival counts go first,
+            // then birth counts and the number of states
+
+            let parts: Vec<&str> = trimmed.split('/').collect();
+
+            if parts.len() != 2 && parts.len() != 3 {
+                return Err(RuleError::WrongFormat(String::from(rule_str)));
+            }
+
+            survival_part = Some(String::from(parts[0]));
+            birth_part = Some(String::from(parts[1]));
+            states_part = parts.get(2).map(|x| String::from(*x));
+        }
+
+        let states = match states_part {
+            Some(states) => match states.parse::<usize>() {
+                Ok(states) if states >= 2 => states,
+                _ => return Err(RuleError::WrongFormat(String::from(rule_str))),
+            },
+            None => 2
+        };
+
+        let birth_part = birth_part.unwrap_or_default();
+        let survival_part = survival_part.unwrap_or_default();
+
+        let is_digit = |c: char| c.is_ascii_digit();
+
+        if birth_part.chars().all(&is_digit) && survival_part.chars().all(&is_digit) {
+
+            let neighbourhood = match shape {
+                Some(shape) => Neighbourhood::new(shape, 1, false),
+                None => Neighbourhood::default(),
+            };
+
+            let birth = parse_counts(&birth_part)?;
+            let survival = parse_counts(&survival_part)?;
+
+            // e.g. 7 neighbours of a hexagonal rule
+            if let Some(&n) = birth.iter().chain(survival.iter()).find(|&&n| n > neighbourhood.max_count()) {
+                return Err(RuleError::WrongNeighboursCount(::std::char::from_digit(n as u32, 10).unwrap()));
+            }
+
+            return Ok(Rule::extended(neighbourhood, &birth, &survival, states));
+        }
+
+        if shape.is_some() {
+            // letters of other neighbourhoods are not supported
+            return Err(RuleError::WrongFormat(String::from(rule_str)));
+        }
+
+        // Hensel letters
+        match (hensel::parse_conditions(&birth_part), hensel::parse_conditions(&survival_part)) {
+            (Some(birth), Some(survival)) => Ok(Rule::isotropic(birth, survival, states)),
+            _ => Err(RuleError::WrongFormat(String::from(rule_str))),
+        }
+    }
+
+    #[inline]
+    pub fn is_born(&self, neighbours_cnt: usize) -> bool {
+        // should a dead cell with given number of live neighbours become alive
+        self.birth.get(neighbours_cnt).cloned().unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn survives(&self, neighbours_cnt: usize) -> bool {
+        // should a live cell with given number of live neighbours stay alive
+        self.survival.get(neighbours_cnt).cloned().unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn get_states(&self) -> usize {
+        self.states
+    }
+
+    #[inline]
+    pub fn get_neighbourhood(&self) -> &Neighbourhood {
+        &self.neighbourhood
+    }
+
+    #[inline]
+    pub fn get_table(&self) -> Option<&RuleTable> {
+        self.table.as_deref()
+    }
+
+    #[inline]
+    pub fn is_life_like(&self) -> bool {
+        // true if only 8 nearest neighbours are counted
+        self.neighbourhood.is_moore() && self.table.is_none()
+    }
+
+    #[inline]
+    pub fn is_totalistic(&self) -> bool {
+        // true if only the number of live neighbours matters,
+        // false for rules in Hensel notation and rule tables
+        self.masks.is_none() && self.table.is_none()
+    }
+
+    pub fn next_cell(&self, cell: Cell, neighbours_cnt: usize) -> Cell {
+        // contents of the cell in the next generation
+        self.transition(cell, self.is_born(neighbours_cnt), self.survives(neighbours_cnt))
+    }
+
+    pub fn next_cell_by_vicinity(&self, cell: Cell, vicinity: &[bool]) -> Cell {
+
+        // same as next_cell, but the configuration of 8 neighbours
+        // returned by Board::get_vicinity is taken into account
+
+        match self.masks {
+            Some((ref birth, ref survival)) => {
+                let mask = hensel::vicinity_mask(vicinity);
+                self.transition(cell, birth[mask], survival[mask])
+            },
+            None => self.next_cell(cell, vicinity.iter().filter(|&&x| x).count()),
+        }
+    }
+
+    fn transition(&self, cell: Cell, born: bool, survives: bool) -> Cell {
+        match cell {
+            // dying cell moves to the next state until it becomes empty
+            Cell::Dying { state } if state + 1 < self.states => Cell::Dying { state: state + 1 },
+            Cell::Dying { .. } => Cell::Empty,
+            Cell::Occupied { gen } if survives => Cell::Occupied { gen: gen + 1 },
+            // live cell that doesn't survive starts dying for Generations rules
+            Cell::Occupied { .. } if self.states > 2 => Cell::Dying { state: 2 },
+            Cell::Occupied { .. } => Cell::Empty,
+            // cells of rule tables don't survive other rules
+            Cell::State { .. } => Cell::Empty,
+            Cell::Empty if born => Cell::Occupied { gen: 1 },
+            Cell::Empty => Cell::Empty,
+        }
+    }
+
+}
+
+fn split_labeled(rule_str: &str) -> Option<Vec<(char, String)>> {
+
+    // splits a string like "B36/S23" into [('B', "36"), ('S', "23")],
+    // the string must start with a B or S label, C labels the number of
+    // states, lowercase c is also a Hensel letter, so it's a label only
+    // after a slash or another label, e.g. "b2sc3" or "B3/S23/c3"
+
+    let mut result: Vec<(char, String)> = Vec::new();
+    let mut after_slash = false;
+
+    for c in rule_str.chars() {
+
+        if c == '/' {
+            after_slash = true;
+            continue;
+        }
+
+        let after_label = match result.last() {
+            Some((_, counts)) => counts.is_empty(),
+            None => false
+        };
+
+        let is_label = match c {
+            'B' | 'b' | 'S' | 's' => true,
+            'C' => !result.is_empty(),
+            'c' => !result.is_empty() && (after_slash || after_label),
+            _ => false
+        };
+
+        after_slash = false;
+
+        if is_label {
+            result.push((c.to_ascii_uppercase(), String::new()));
+        } else {
+            match result.last_mut() {
+                Some(&mut (_, ref mut counts)) => counts.push(c),
+                None => return None,
+            }
+        }
+    }
+
+    Some(result)
+}
+
+fn parse_extended(rule_str: &str, normalized: &str) -> Result<Rule, RuleError> {
+
+    // parses HROT notation, e.g. "R5,C0,M1,S34..58,B34..45,NM", counts
+    // of survival and birth are separated by commas, e.g. "S2,4..6"
+
+    let err = || RuleError::WrongFormat(String::from(rule_str));
+
+    let mut range: Option<usize> = None;
+    let mut states = 2;
+    let mut include_center = false;
+    let mut birth: Vec<usize> = Vec::new();
+    let mut survival: Vec<usize> = Vec::new();
+    let mut shape_str = String::from("M");
+
+    // label of the list that the following counts belong to
+    let mut counts_label: Option<char> = None;
+
+    for token in normalized.split(',').map(|x| x.trim()) {
+
+        let label = match token.chars().next() {
+            Some(label) => label,
+            None => return Err(err()),
+        };
+
+        if label.is_ascii_digit() {
+            match counts_label {
+                Some('S') => survival.extend(parse_count_range(token).ok_or_else(&err)?),
+                Some('B') => birth.extend(parse_count_range(token).ok_or_else(&err)?),
+                _ => return Err(err()),
+            }
+            continue;
+        }
+
+        let value = &token[1..];
+        counts_label = None;
+
+        match label {
+            'R' => range = match value.parse::<usize>() {
+                Ok(range) if (1..=MAX_RANGE).contains(&range) => Some(range),
+                _ => return Err(err()),
+            },
+            // 0 and 1 are the same as 2 states
+            'C' => states = match value.parse::<usize>() {
+                Ok(states) => states.max(2),
+                Err(_) => return Err(err()),
+            },
+            'M' => include_center = match value {
+                "0" => false,
+                "1" => true,
+                _ => return Err(err()),
+            },
+            'S' | 'B' => {
+                counts_label = Some(label);
+                if !value.is_empty() {
+                    let counts = parse_count_range(value).ok_or_else(&err)?;
+                    if label == 'S' { survival.extend(counts) } else { birth.extend(counts) }
+                }
+            },
+            'N' => shape_str = String::from(value),
+            _ => return Err(err()),
+        }
+    }
+
+    let range = range.ok_or_else(&err)?;
+
+    let shape = match shape_str.as_str() {
+        "M" => Shape::Moore,
+        "N" => Shape::VonNeumann,
+        "C" => Shape::Circular,
+        "H" => Shape::Hexagonal,
+        _ if shape_str.starts_with('W') => {
+            let weights: Option<Vec<usize>> = shape_str[1..].chars().
+                map(|c| c.to_digit(16).map(|x| x as usize)).collect();
+            match weights {
+                Some(ref weights) if weights.len() == (2 * range + 1) * (2 * range + 1) =>
+                    Shape::Weighted(weights.clone()),
+                _ => return Err(err()),
+            }
+        },
+        _ => return Err(err()),
+    };
+
+    let neighbourhood = Neighbourhood::new(shape, range, include_center);
+    let max_count = neighbourhood.max_count();
+
+    if birth.iter().chain(survival.iter()).any(|&n| n > max_count) {
+        return Err(err());
+    }
+
+    Ok(Rule::extended(neighbourhood, &birth, &survival, states))
+}
+
+fn parse_count_range(range_str: &str) -> Option<Vec<usize>> {
+
+    // parses "34..58" or a single count "5"
+
+    let mut bounds = range_str.splitn(2, "..").map(|x| x.parse::<usize>());
+
+    let first = match bounds.next() {
+        Some(Ok(first)) => first,
+        _ => return None,
+    };
+
+    let last = match bounds.next() {
+        Some(Ok(last)) => last,
+        Some(Err(_)) => return None,
+        None => first,
+    };
+
+    if first > last {
+        return None;
+    }
+
+    Some((first..last + 1).collect())
+}
+
+fn format_count_ranges(counts: &[bool]) -> String {
+
+    // joins consecutive counts into ranges, e.g. "2,4..6"
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut n = 0;
+
+    while n < counts.len() {
+        if !counts[n] {
+            n += 1;
+            continue;
+        }
+
+        let first = n;
+        while n + 1 < counts.len() && counts[n + 1] {
+            n += 1;
+        }
+
+        ranges.push(if first == n { first.to_string() } else { format!("{}..{}", first, n) });
+        n += 1;
+    }
+
+    ranges.join(",")
+}
+
+fn parse_counts(counts: &str) -> Result<Vec<usize>, RuleError> {
+
+    let mut result = Vec::new();
+
+    for c in counts.chars() {
+        match c.to_digit(10) {
+            Some(n) if n as usize <= MAX_NEIGHBOURS => result.push(n as usize),
+            _ => return Err(RuleError::WrongNeighboursCount(c)),
+        }
+    }
+
+    Ok(result)
+}
+
+impl FromStr for Rule {
+
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::parse(s)
+    }
+
+}
+
+impl Display for Rule {
+
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+
+        if let Some(ref table) = self.table {
+            return write!(f, "{}", table.get_name());
+        }
+
+        if let Some((ref birth, ref survival)) = self.masks {
+            write!(f, "B{}/S{}", hensel::format_conditions(birth), hensel::format_conditions(survival))?;
+            if self.states > 2 {
+                write!(f, "/C{}", self.states)?;
+            }
+            return Ok(());
+        }
+
+        let nearest = self.neighbourhood.range == 1 && !self.neighbourhood.include_center;
+
+        // suffix of rules of the nearest cells
+        let suffix = match self.neighbourhood.shape {
+            Shape::Moore if nearest => Some(""),
+            Shape::Hexagonal if nearest => Some("H"),
+            Shape::VonNeumann if nearest => Some("V"),
+            _ => None
+        };
+
+        if suffix.is_none() {
+
+            let neighbourhood = match self.neighbourhood.shape {
+                Shape::Moore => String::from("M"),
+                Shape::VonNeumann => String::from("N"),
+                Shape::Circular => String::from("C"),
+                Shape::Hexagonal => String::from("H"),
+                Shape::Weighted(ref weights) => format!("W{}", weights.iter().
+                    map(|&x| format!("{:X}", x)).collect::<String>()),
+            };
+
+            // 2 states are written as 0 in HROT notation
+            return write!(f, "R{},C{},M{},S{},B{},N{}", self.neighbourhood.range,
+                          if self.states > 2 { self.states } else { 0 },
+                          if self.neighbourhood.include_center { 1 } else { 0 },
+                          format_count_ranges(&self.survival), format_count_ranges(&self.birth),
+                          neighbourhood);
+        }
+
+        let birth: String = (0..self.birth.len()).filter(|&n| self.birth[n]).
+                                                  map(|n| n.to_string()).collect();
+
+        let survival: String = (0..self.survival.len()).filter(|&n| self.survival[n]).
+                                                        map(|n| n.to_string()).collect();
+
+        let suffix = suffix.unwrap_or_default();
+
+        if self.states > 2 {
+            write!(f, "B{}/S{}/C{}{}", birth, survival, self.states, suffix)
+        } else {
+            write!(f, "B{}/S{}{}", birth, survival, suffix)
+        }
+    }
+
+}
+
+
+#[test]
+fn test_parse_rule_notations() {
+    let highlife = Rule::new(&[3, 6], &[2, 3]);
+
+    assert_eq!(Rule::parse("B36/S23").unwrap(), highlife);
+    assert_eq!(Rule::parse("b36s23").unwrap(), highlife);
+    assert_eq!(Rule::parse("S23/B36").unwrap(), highlife);
+    assert_eq!(Rule::parse("23/36").unwrap(), highlife);
+    assert_eq!(Rule::parse(" B36/S23 ").unwrap(), highlife);
+}
+
+#[test]
+fn test_parse_rule_empty_parts() {
+    // seeds
+    assert_eq!(Rule::parse("B2/S").unwrap(), Rule::new(&[2], &[]));
+    assert_eq!(Rule::parse("/2").unwrap(), Rule::new(&[2], &[]));
+    assert_eq!(Rule::parse("B2").unwrap(), Rule::new(&[2], &[]));
+}
+
+#[test]
+fn test_parse_rule_errors() {
+    assert!(Rule::parse("").is_err());
+    assert!(Rule::parse("B39/S23").is_err());
+    assert!(Rule::parse("B3/S23/S4").is_err());
+    assert!(Rule::parse("23").is_err());
+    assert!(Rule::parse("Bx/S23").is_err());
+    assert!(Rule::parse("B2/S/C1").is_err());
+    assert!(Rule::parse("B2/S/Cx").is_err());
+    assert!(Rule::parse("2/3/4/5").is_err());
+}
+
+#[test]
+fn test_parse_generations() {
+    let brians_brain = Rule::generations(&[2], &[], 3);
+
+    assert_eq!(Rule::parse("B2/S/C3").unwrap(), brians_brain);
+    assert_eq!(Rule::parse("b2sc3").unwrap(), brians_brain);
+    assert_eq!(Rule::parse("/2/3").unwrap(), brians_brain);
+    assert_eq!(Rule::parse("345/2/4").unwrap(), Rule::generations(&[2], &[3, 4, 5], 4));
+
+    // two states are the same as a Life-like rule
+    assert_eq!(Rule::parse("B3/S23/C2").unwrap(), Rule::default());
+
+    assert_eq!(brians_brain.to_string(), "B2/S/C3");
+}
+
+#[test]
+fn test_parse_larger_than_life() {
+    let bosco = Rule::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+
+    assert!(!bosco.is_life_like());
+    assert_eq!(bosco.get_neighbourhood().max_count(), 121);
+    assert!(bosco.is_born(34) && bosco.is_born(45) && !bosco.is_born(46));
+    assert!(bosco.survives(58) && !bosco.survives(33));
+    assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+
+    let rule = Rule::parse("r2,c3,m0,s2,4..5,b3,nn").unwrap();
+    assert_eq!(rule.get_states(), 3);
+    assert_eq!(rule.get_neighbourhood().shape, Shape::VonNeumann);
+    assert_eq!(rule.to_string(), "R2,C3,M0,S2,4..5,B3,NN");
+
+    let weighted = Rule::parse("R1,C0,M0,S2..3,B3,NW121202121").unwrap();
+    assert_eq!(weighted.get_neighbourhood().max_count(), 12);
+    assert_eq!(weighted.to_string(), "R1,C0,M0,S2..3,B3,NW121202121");
+
+    // range 1 Moore neighbourhood is Life-like
+    assert_eq!(Rule::parse("R1,C0,M0,S2..3,B3,NM").unwrap(), Rule::default());
+
+    assert!(Rule::parse("R0,C0,M0,S1,B1,NM").is_err());
+    assert!(Rule::parse("R1,C0,M0,S2..9,B3,NM").is_err());
+    assert!(Rule::parse("R1,C0,M2,S2,B3,NM").is_err());
+    assert!(Rule::parse("R1,C0,M0,S3..2,B3,NM").is_err());
+    assert!(Rule::parse("R1,C0,M0,S2,B3,NX").is_err());
+    assert!(Rule::parse("R1,C0,M0,S2,B3,NW1212").is_err());
+    assert!(Rule::parse("C0,M0,S2,B3,NM").is_err());
+}
+
+#[test]
+fn test_parse_hensel() {
+    let rule = Rule::parse("B2-a/S12").unwrap();
+
+    assert!(!rule.is_totalistic());
+    assert_eq!(rule.to_string(), "B2-a/S12");
+
+    // vicinity starts from the west neighbour clockwise,
+    // north and north-east neighbours are adjacent
+    let adjacent = [false, false, true, true, false, false, false, false];
+    let apart = [false, false, true, false, false, false, true, false];
+
+    assert_eq!(rule.next_cell_by_vicinity(Cell::Empty, &adjacent), Cell::Empty);
+    assert_eq!(rule.next_cell_by_vicinity(Cell::Empty, &apart), Cell::Occupied { gen: 1 });
+
+    assert_eq!(Rule::parse("b3s2-i34q").unwrap().to_string(), "B3/S2-i34q");
+    assert_eq!(Rule::parse("B2c/S/C3").unwrap().to_string(), "B2c/S/C3");
+    assert_eq!(Rule::parse("23-k/2a").unwrap().to_string(), "B2a/S23-k");
+
+    // all letters of a count are the same as the count itself
+    assert_eq!(Rule::parse("B3/S2ceaikn3").unwrap(), Rule::default());
+
+    assert!(Rule::parse("B2x/S23").is_err());
+    assert!(Rule::parse("B1a/S23").is_err());
+}
+
+#[test]
+fn test_parse_neighbourhood_suffix() {
+    let hex = Rule::parse("B2/S34H").unwrap();
+    assert_eq!(hex.get_neighbourhood().shape, Shape::Hexagonal);
+    assert_eq!(hex.to_string(), "B2/S34H");
+
+    let von_neumann = Rule::parse("b2s1v").unwrap();
+    assert_eq!(von_neumann.get_neighbourhood().max_count(), 4);
+    assert_eq!(von_neumann.to_string(), "B2/S1V");
+
+    assert_eq!(Rule::parse("34/2/3H").unwrap().to_string(), "B2/S34/C3H");
+    assert_eq!(Rule::parse("R1,C0,M0,S3..4,B2,NH").unwrap(), hex);
+
+    assert!(Rule::parse("B7/S34H").is_err());
+    assert!(Rule::parse("B2a/S34H").is_err());
+}
+
+#[test]
+fn test_parse_rule_table() {
+    let wireworld = Rule::parse("wireworld").unwrap();
+
+    assert_eq!(wireworld.get_states(), 4);
+    assert!(!wireworld.is_life_like());
+    assert!(!wireworld.is_totalistic());
+    assert_eq!(wireworld.to_string(), "WireWorld");
+    assert_eq!(Rule::parse("WireWorld").unwrap(), wireworld);
+
+    let table = RuleTable::parse("@RULE Spread\n@TABLE\nn_states:2\nneighborhood:vonNeumann\n\
+                                  symmetries:permute\n0,1,0,0,0,1\n").unwrap();
+    let rule = Rule::from_table(table);
+
+    assert_eq!(rule.get_neighbourhood().shape, Shape::VonNeumann);
+    assert_eq!(rule.to_string(), "Spread");
+}
+
+#[test]
+fn test_rule_display() {
+    assert_eq!(Rule::default().to_string(), "B3/S23");
+    assert_eq!(Rule::parse("34678/3678").unwrap().to_string(), "B3678/S34678");
+}
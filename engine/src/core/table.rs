@@ -0,0 +1,713 @@
+//! Rule tables in Golly's `.rule` format.
+//!
+//! A rule table lists transitions of a cell with up to 256 states: the
+//! state of the cell itself, the states of its neighbours and the new state
+//! of the cell, e.g. Wireworld is a built-in table:
+//! ```text
+//! @RULE WireWorld
+//! @TABLE
+//! n_states:4
+//! neighborhood:Moore
+//! symmetries:permute
+//! var a={0,1,2,3}
+//! ...
+//! 1,a,b,c,d,e,f,g,h,2
+//! ```
+//!
+//! Neighbours of the Moore neighbourhood are listed clockwise starting from
+//! the north one, von Neumann ones are N, E, S, W. Variables stand for any
+//! of their states, a variable that appears more than once in a transition
+//! or gives the new state takes the same value everywhere. Transitions are
+//! checked in order, the first matching one wins and cells without any
+//! matching transition keep their state. Symmetries allow a transition to
+//! match rotated, reflected or, for "permute", arbitrarily shuffled
+//! neighbours.
+//!
+//! The optional `@COLORS` section gives colors of states as "state r g b"
+//! lines or as a gradient "r1 g1 b1 r2 g2 b2" of all live states. Other
+//! sections, e.g. `@ICONS`, are ignored.
+
+use std::collections::{HashMap, HashSet};
+
+use board::{Board, Cell, Coord};
+use core::neighbourhood::Shape;
+use core::rule::RuleError;
+
+// maximum number of cell states including the empty one
+pub const MAX_STATES: usize = 256;
+
+// neighbours in the order of transitions, clockwise starting from the north one
+const MOORE: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+const VON_NEUMANN: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+const WIREWORLD: &str = "@RULE WireWorld
+
+A cellular automaton for simulating electronic circuits, electron heads
+move along wires and turn into tails, a wire cell becomes a head if one
+or two of its neighbours are heads.
+
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+
+# electron head becomes a tail
+1,a,b,c,d,e,f,g,h,2
+# electron tail becomes a wire
+2,a,b,c,d,e,f,g,h,3
+# wire becomes a head if one or two neighbours are heads
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+
+@COLORS
+0 48 48 48
+1 0 128 255
+2 255 255 255
+3 255 128 0
+";
+
+// tables that can be used by their names
+const BUILTIN: [(&str, &str); 1] = [
+    ("WireWorld", WIREWORLD),
+];
+
+// bit set of cell states
+type StateSet = [u64; 4];
+
+
+#[derive(Debug, PartialEq, Eq)]
+struct Transition {
+    // allowed states of the cell itself and its neighbours
+    inputs: Vec<StateSet>,
+    output: usize,
+    // neighbours match the inputs in any order
+    permute: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleTable {
+    name: String,
+    states: usize,
+    shape: Shape,
+    // transitions with variables replaced by sets of states and all
+    // symmetric versions of every transition except permuted ones
+    transitions: Vec<Transition>,
+    // indices of transitions for every state of the cell itself in order
+    by_state: Vec<Vec<usize>>,
+    colors: Vec<Option<[u8; 3]>>,
+}
+
+#[derive(Clone, PartialEq)]
+enum Term {
+    State(usize),
+    Var(String),
+}
+
+enum Symmetry {
+    // permutations of neighbours, the i-th neighbour of a transition
+    // is moved to the position given by its i-th element
+    Group(Vec<Vec<usize>>),
+    Permute,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Rule,
+    Table,
+    Colors,
+    Other,
+}
+
+#[inline]
+fn contains(set: &StateSet, state: usize) -> bool {
+    state < MAX_STATES && (set[state >> 6] >> (state & 63)) & 1 == 1
+}
+
+fn state_set(states: &[usize]) -> StateSet {
+    let mut set = [0; 4];
+    for &state in states {
+        set[state >> 6] |= 1 << (state & 63);
+    }
+    set
+}
+
+fn assign(sets: &[StateSet], cells: &[usize], cell: usize,
+          owners: &mut [Option<usize>], visited: &mut [bool]) -> bool {
+
+    // finds a set for the cell, cells that own sets already may move to
+    // other ones, i.e. it's an augmenting path of bipartite matching
+
+    for (idx, set) in sets.iter().enumerate() {
+        if !visited[idx] && contains(set, cells[cell]) {
+            visited[idx] = true;
+            let free = match owners[idx] {
+                Some(owner) => assign(sets, cells, owner, owners, visited),
+                None => true,
+            };
+
+            if free {
+                owners[idx] = Some(cell);
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+impl Transition {
+
+    fn matches(&self, cells: &[usize]) -> bool {
+
+        if !self.permute {
+            return self.inputs.iter().zip(cells.iter()).all(|(set, &state)| contains(set, state));
+        }
+
+        if !contains(&self.inputs[0], cells[0]) {
+            return false;
+        }
+
+        // every neighbour needs its own set of the inputs
+        let sets = &self.inputs[1..];
+        let neighbours = &cells[1..];
+
+        let mut owners = [None; 8];
+
+        (0..neighbours.len()).all(|cell| {
+            let mut visited = [false; 8];
+            assign(sets, neighbours, cell, &mut owners, &mut visited)
+        })
+    }
+
+}
+
+fn parse_symmetry(name: &str, neighbours: usize) -> Option<Symmetry> {
+
+    let rotate = |k: usize| -> Vec<usize> { (0..neighbours).map(|i| (i + k) % neighbours).collect() };
+    let reflect = |k: usize| -> Vec<usize> {
+        (0..neighbours).map(|i| (neighbours - i + k) % neighbours).collect()
+    };
+
+    // a quarter turn moves Moore neighbours by 2 positions
+    let quarter = neighbours / 4;
+
+    let group = match name {
+        "none" => vec![rotate(0)],
+        "rotate4" => (0..4).map(|k| rotate(k * quarter)).collect(),
+        "rotate8" if neighbours == 8 => (0..8).map(rotate).collect(),
+        "reflect_horizontal" => vec![rotate(0), reflect(0)],
+        "rotate4reflect" => (0..4).map(|k| rotate(k * quarter)).
+            chain((0..4).map(|k| reflect(k * quarter))).collect(),
+        "rotate8reflect" if neighbours == 8 => (0..8).map(rotate).chain((0..8).map(reflect)).collect(),
+        "permute" => return Some(Symmetry::Permute),
+        _ => return None,
+    };
+
+    Some(Symmetry::Group(group))
+}
+
+fn wrong_table(line: usize, reason: &str) -> RuleError {
+    RuleError::WrongTable(line, String::from(reason))
+}
+
+fn parse_term(token: &str, states: usize, vars: &HashMap<String, Vec<usize>>) -> Option<Term> {
+    match token.parse::<usize>() {
+        Ok(state) if state < states => Some(Term::State(state)),
+        Ok(_) => None,
+        Err(_) if vars.contains_key(token) => Some(Term::Var(String::from(token))),
+        Err(_) => None,
+    }
+}
+
+fn compile(terms: &[Term], vars: &HashMap<String, Vec<usize>>, symmetry: &Symmetry) -> Vec<Transition> {
+
+    // replaces variables by sets of their states and adds symmetric
+    // versions of the transition, variables that appear more than once
+    // or give the output are bound, i.e. every combination of their
+    // values is a separate transition
+
+    let (inputs, output) = terms.split_at(terms.len() - 1);
+
+    let mut bound: Vec<&str> = Vec::new();
+
+    for term in terms.iter() {
+        if let Term::Var(ref name) = *term {
+            let count = inputs.iter().filter(|&x| x == term).count();
+            if (count > 1 || *term == output[0]) && !bound.contains(&name.as_str()) {
+                bound.push(name);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut seen: HashSet<Vec<StateSet>> = HashSet::new();
+
+    // indices of values of bound variables
+    let mut choice = vec![0; bound.len()];
+
+    loop {
+
+        let value = |name: &str| -> Option<usize> {
+            bound.iter().position(|&x| x == name).map(|k| vars[name][choice[k]])
+        };
+
+        let sets: Vec<StateSet> = inputs.iter().map(|term| match *term {
+            Term::State(state) => state_set(&[state]),
+            Term::Var(ref name) => match value(name) {
+                Some(state) => state_set(&[state]),
+                None => state_set(&vars[name]),
+            }
+        }).collect();
+
+        let new_state = match output[0] {
+            Term::State(state) => state,
+            Term::Var(ref name) => value(name).unwrap(),
+        };
+
+        let mut add = |sets: Vec<StateSet>, permute: bool| {
+            if seen.insert(sets.clone()) {
+                result.push(Transition { inputs: sets, output: new_state, permute });
+            }
+        };
+
+        match *symmetry {
+            Symmetry::Group(ref group) => {
+                for perm in group {
+                    let mut permuted = sets.clone();
+                    for (i, &j) in perm.iter().enumerate() {
+                        permuted[j + 1] = sets[i + 1];
+                    }
+                    add(permuted, false);
+                }
+            },
+            Symmetry::Permute => {
+                // neighbours are matched in any order, so the order
+                // of their sets doesn't matter
+                let mut sorted = sets.clone();
+                sorted[1..].sort();
+                add(sorted, true);
+            },
+        }
+
+        // next combination of values of bound variables
+        let mut k = 0;
+        while k < bound.len() {
+            choice[k] += 1;
+            if choice[k] < vars[bound[k]].len() {
+                break;
+            }
+            choice[k] = 0;
+            k += 1;
+        }
+
+        if k == bound.len() {
+            break;
+        }
+    }
+
+    result
+}
+
+fn parse_colors(numbers: &[usize], states: usize, colors: &mut [Option<[u8; 3]>]) -> bool {
+
+    // "state r g b" or a gradient "r1 g1 b1 r2 g2 b2" of states 1 and above,
+    // returns false if the line is not valid
+
+    if numbers.iter().skip(if numbers.len() == 4 { 1 } else { 0 }).any(|&x| x > 255) {
+        return false;
+    }
+
+    match numbers.len() {
+        4 if numbers[0] < states => {
+            colors[numbers[0]] = Some([numbers[1] as u8, numbers[2] as u8, numbers[3] as u8]);
+        },
+        6 => {
+            for (state, slot) in colors.iter_mut().enumerate().take(states).skip(1) {
+                let t = if states > 2 { (state - 1) as f64 / (states - 2) as f64 } else { 0.0 };
+                let mut color = [0; 3];
+                for c in 0..3 {
+                    let from = numbers[c] as f64;
+                    let to = numbers[c + 3] as f64;
+                    color[c] = (from + (to - from) * t).round() as u8;
+                }
+                *slot = Some(color);
+            }
+        },
+        _ => return false,
+    }
+
+    true
+}
+
+impl RuleTable {
+
+    pub fn parse(content: &str) -> Result<Self, RuleError> {
+
+        let mut section: Option<Section> = None;
+
+        let mut name: Option<String> = None;
+        let mut states: Option<usize> = None;
+        let mut shape = Shape::Moore;
+        let mut symmetry = Symmetry::Group(vec![(0..MOORE.len()).collect()]);
+        let mut symmetry_name = String::from("none");
+
+        let mut vars: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut transitions: Vec<Transition> = Vec::new();
+        let mut has_table = false;
+
+        // colors are applied once the number of states is known
+        let mut color_lines: Vec<(usize, Vec<usize>)> = Vec::new();
+
+        for (idx, line) in content.lines().enumerate() {
+
+            let line_no = idx + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.starts_with('@') {
+
+                let mut words = line.split_whitespace();
+
+                section = Some(match words.next() {
+                    Some("@RULE") => {
+                        name = words.next().map(String::from);
+                        Section::Rule
+                    },
+                    Some("@TABLE") => {
+                        has_table = true;
+                        Section::Table
+                    },
+                    Some("@COLORS") => Section::Colors,
+                    _ => Section::Other,
+                });
+
+                continue;
+            }
+
+            if line.is_empty() {
+                continue;
+            }
+
+            match section {
+
+                None => return Err(wrong_table(line_no, "expected @RULE")),
+
+                Some(Section::Table) => {
+
+                    let neighbours = match shape {
+                        Shape::VonNeumann => VON_NEUMANN.len(),
+                        _ => MOORE.len(),
+                    };
+
+                    if let Some(colon) = line.find(':') {
+
+                        let key = line[..colon].trim();
+                        let value = line[colon + 1..].trim();
+
+                        if !transitions.is_empty() || !vars.is_empty() {
+                            return Err(wrong_table(line_no, "parameters must precede variables and transitions"));
+                        }
+
+                        match key {
+                            "n_states" => states = match value.parse::<usize>() {
+                                Ok(n) if (2..=MAX_STATES).contains(&n) => Some(n),
+                                _ => return Err(wrong_table(line_no, "number of states must be from 2 to 256")),
+                            },
+                            "neighborhood" => shape = match value {
+                                "Moore" => Shape::Moore,
+                                "vonNeumann" => Shape::VonNeumann,
+                                _ => return Err(wrong_table(line_no, "only Moore and vonNeumann neighborhoods are supported")),
+                            },
+                            "symmetries" => symmetry_name = String::from(value),
+                            _ => return Err(wrong_table(line_no, "unknown parameter")),
+                        }
+
+                        let neighbours = match shape {
+                            Shape::VonNeumann => VON_NEUMANN.len(),
+                            _ => MOORE.len(),
+                        };
+
+                        // symmetries depend on the neighbourhood, which may be given later
+                        symmetry = match parse_symmetry(&symmetry_name, neighbours) {
+                            Some(symmetry) => symmetry,
+                            None => return Err(wrong_table(line_no, "unknown symmetries")),
+                        };
+
+                        continue;
+                    }
+
+                    let states = match states {
+                        Some(states) => states,
+                        None => return Err(wrong_table(line_no, "n_states must be given first")),
+                    };
+
+                    if let Some(definition) = line.strip_prefix("var ") {
+
+                        // "var a={0,1,2}", other variables may be used as values
+                        let (var_name, values) = match definition.find('=') {
+                            Some(eq) => (definition[..eq].trim(), definition[eq + 1..].trim()),
+                            None => return Err(wrong_table(line_no, "wrong variable")),
+                        };
+
+                        if !values.starts_with('{') || !values.ends_with('}') || var_name.is_empty() ||
+                            var_name.chars().all(|c| c.is_ascii_digit()) {
+                            return Err(wrong_table(line_no, "wrong variable"));
+                        }
+
+                        let mut var_states: Vec<usize> = Vec::new();
+
+                        for token in values[1..values.len() - 1].split(',').map(str::trim) {
+                            match parse_term(token, states, &vars) {
+                                Some(Term::State(state)) => var_states.push(state),
+                                Some(Term::Var(ref other)) => var_states.extend(vars[other].iter().cloned()),
+                                None => return Err(wrong_table(line_no, "wrong variable value")),
+                            }
+                        }
+
+                        var_states.sort();
+                        var_states.dedup();
+
+                        if var_states.is_empty() {
+                            return Err(wrong_table(line_no, "empty variable"));
+                        }
+
+                        vars.insert(String::from(var_name), var_states);
+                        continue;
+                    }
+
+                    // "0,1,2,..." or "012..." for single digit states without variables
+                    let tokens: Vec<String> = if line.contains(',') {
+                        line.split(',').map(|x| String::from(x.trim())).collect()
+                    } else {
+                        line.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_string()).collect()
+                    };
+
+                    if tokens.len() != neighbours + 2 {
+                        return Err(wrong_table(line_no, "wrong number of states in transition"));
+                    }
+
+                    let mut terms: Vec<Term> = Vec::new();
+
+                    for token in tokens.iter() {
+                        match parse_term(token, states, &vars) {
+                            Some(term) => terms.push(term),
+                            None => return Err(wrong_table(line_no, "wrong state or unknown variable")),
+                        }
+                    }
+
+                    // output variable must get its value from the inputs
+                    if let Some(Term::Var(output)) = terms.last() {
+                        if !terms[..neighbours + 1].iter().any(|x| *x == Term::Var(output.clone())) {
+                            return Err(wrong_table(line_no, "output variable is not bound"));
+                        }
+                    }
+
+                    transitions.extend(compile(&terms, &vars, &symmetry));
+                },
+
+                Some(Section::Colors) => {
+                    let numbers: Result<Vec<usize>, _> = line.split_whitespace().map(|x| x.parse::<usize>()).collect();
+                    match numbers {
+                        Ok(numbers) => color_lines.push((line_no, numbers)),
+                        Err(_) => return Err(wrong_table(line_no, "wrong color")),
+                    }
+                },
+
+                // description of the rule and unsupported sections
+                Some(_) => {},
+            }
+        }
+
+        let lines = content.lines().count();
+
+        let name = match name {
+            Some(name) => name,
+            None => return Err(wrong_table(lines, "missing @RULE name")),
+        };
+
+        let states = match (has_table, states) {
+            (true, Some(states)) => states,
+            _ => return Err(wrong_table(lines, "missing @TABLE or n_states")),
+        };
+
+        let mut colors = vec![None; states];
+
+        for (line_no, numbers) in color_lines {
+            if !parse_colors(&numbers, states, &mut colors) {
+                return Err(wrong_table(line_no, "wrong color"));
+            }
+        }
+
+        let by_state = (0..states).map(|state| {
+            transitions.iter().enumerate().
+                filter(|&(_, t)| contains(&t.inputs[0], state)).
+                map(|(idx, _)| idx).collect()
+        }).collect();
+
+        Ok(RuleTable {
+            name,
+            states,
+            shape,
+            transitions,
+            by_state,
+            colors,
+        })
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        // built-in table with the given name, case is ignored
+        BUILTIN.iter().find(|&&(table_name, _)| table_name.eq_ignore_ascii_case(name)).
+            map(|&(_, content)| RuleTable::parse(content).unwrap())
+    }
+
+    #[inline]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn get_states(&self) -> usize {
+        self.states
+    }
+
+    #[inline]
+    pub fn get_shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn get_color(&self, state: usize) -> Option<[u8; 3]> {
+        // color of a state given in the @COLORS section
+        self.colors.get(state).cloned().unwrap_or(None)
+    }
+
+    fn get_offsets(&self) -> &'static [(isize, isize)] {
+        match self.shape {
+            Shape::VonNeumann => &VON_NEUMANN,
+            _ => &MOORE,
+        }
+    }
+
+    pub fn next_state(&self, cells: &[usize]) -> usize {
+
+        // new state of a cell given its own state followed by the states
+        // of its neighbours in the order of transitions
+
+        self.by_state.get(cells[0]).
+            and_then(|indices| indices.iter().map(|&idx| &self.transitions[idx]).find(|t| t.matches(cells))).
+            map(|t| t.output).
+            unwrap_or(cells[0])
+    }
+
+}
+
+pub fn next_cells(board: &Board, table: &RuleTable) -> Vec<(Coord, Cell)> {
+
+    // returns coordinates and contents of cells that will be non empty
+    // in the next generation, only allocated cells are checked, i.e.
+    // non empty cells and their neighbours
+
+    let offsets = table.get_offsets();
+    let mut cells = vec![0; offsets.len() + 1];
+
+    let mut result = Vec::new();
+
+    for desc in board.iter() {
+
+        let Coord { col, row } = desc.coord;
+
+        cells[0] = desc.state;
+        for (k, &(dx, dy)) in offsets.iter().enumerate() {
+            cells[k + 1] = board.get_cell_state(col + dx, row + dy);
+        }
+
+        let next = match table.next_state(&cells) {
+            0 => continue,
+            // state 1 is kept as a live cell, so it gets older
+            1 if desc.state == 1 => Cell::Occupied { gen: desc.gen + 1 },
+            1 => Cell::Occupied { gen: 1 },
+            state => Cell::State { state },
+        };
+
+        result.push((desc.coord, next));
+    }
+
+    result
+}
+
+
+#[test]
+fn test_parse_wireworld() {
+    let table = RuleTable::builtin("wireworld").unwrap();
+
+    assert_eq!(table.get_name(), "WireWorld");
+    assert_eq!(table.get_states(), 4);
+    assert_eq!(table.get_color(3), Some([255, 128, 0]));
+
+    // heads become tails and tails become wires
+    assert_eq!(table.next_state(&[1, 0, 0, 3, 3, 3, 0, 0, 0]), 2);
+    assert_eq!(table.next_state(&[2, 0, 0, 3, 3, 3, 0, 0, 0]), 3);
+
+    // wire with one or two heads around in any position
+    assert_eq!(table.next_state(&[3, 0, 0, 0, 0, 0, 0, 1, 0]), 1);
+    assert_eq!(table.next_state(&[3, 1, 0, 0, 0, 2, 0, 1, 0]), 1);
+    assert_eq!(table.next_state(&[3, 1, 0, 1, 0, 0, 0, 1, 0]), 3);
+    assert_eq!(table.next_state(&[0, 1, 1, 1, 0, 0, 0, 0, 0]), 0);
+}
+
+#[test]
+fn test_parse_table() {
+    let table = RuleTable::parse("@RULE Test\n@TABLE\nn_states:3\nneighborhood:vonNeumann\n\
+                                  symmetries:rotate4\nvar a={1,2}\nvar b={a,0}\n\
+                                  # bound variable\n0,a,0,a,0,a\n01000 2\n").unwrap();
+
+    assert_eq!(table.get_shape(), &Shape::VonNeumann);
+    assert_eq!(table.get_color(1), None);
+
+    // both neighbours must be in the same state
+    assert_eq!(table.next_state(&[0, 2, 0, 2, 0]), 2);
+    assert_eq!(table.next_state(&[0, 0, 1, 0, 1]), 1);
+    assert_eq!(table.next_state(&[0, 1, 0, 2, 0]), 0);
+
+    // rotations of the compact transition
+    assert_eq!(table.next_state(&[0, 0, 0, 1, 0]), 2);
+    assert_eq!(table.next_state(&[1, 0, 0, 1, 0]), 1);
+
+    assert_eq!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:2\n0,1,0,0,0,0,0,0,1,x\n"),
+               Err(RuleError::WrongTable(4, String::from("wrong state or unknown variable"))));
+    assert!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:2\nvar a={0,1}\n0,1,0,0,0,0,0,0,0,a\n").is_err());
+    assert!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:2\n0,1,0,0,0,0,0,0,1,2\n").is_err());
+    assert!(RuleTable::parse("@RULE Test\n@TABLE\nn_states:2\nneighborhood:hexagonal\n").is_err());
+    assert!(RuleTable::parse("@RULE Test\n@COLORS\n1 255 0 0\n").is_err());
+}
+
+#[test]
+fn test_permute_table() {
+    let table = RuleTable::parse("@RULE Test\n@TABLE\nn_states:3\nsymmetries:permute\n\
+                                  var a={0,1}\nvar b={1,2}\n0,a,b,0,0,0,0,0,0,2\n").unwrap();
+
+    // a permuted transition is kept once
+    assert_eq!(table.transitions.len(), 1);
+
+    // the state 1 matches both variables, so it gives a up for the state 0
+    assert_eq!(table.next_state(&[0, 1, 0, 0, 0, 0, 0, 0, 0]), 2);
+    assert_eq!(table.next_state(&[0, 0, 0, 0, 0, 0, 0, 2, 1]), 2);
+    assert_eq!(table.next_state(&[0, 0, 0, 0, 2, 0, 0, 0, 0]), 2);
+    assert_eq!(table.next_state(&[0, 2, 0, 0, 2, 0, 0, 0, 0]), 0);
+    assert_eq!(table.next_state(&[0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+    assert_eq!(table.next_state(&[1, 1, 0, 0, 0, 0, 0, 0, 0]), 1);
+}
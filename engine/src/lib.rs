@@ -1,6 +1,6 @@
 mod board;
 mod core;
 
-pub use board::{Board, BoardIterator, CellDesc, Coord};
-pub use core::{Engine, loader};
+pub use board::{Board, BoardIterator, Cell, CellDesc, Coord, Grid, Topology, TopologyError, transform};
+pub use core::{Engine, Neighbourhood, Rule, RuleError, RuleTable, Shape, loader};
 
@@ -11,6 +11,10 @@ extern crate ui;
 
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::thread;
+use std::process;
+use std::fs;
+use std::io;
 
 use find_folder::Search;
 use piston_window::{PistonWindow, WindowSettings};
@@ -18,8 +22,9 @@ use piston_window::{PistonWindow, WindowSettings};
 use opengl_graphics::glyph_cache::GlyphCache;
 
 use clap::{App, Arg};
-use engine::loader::from_file as load_from_file;
-use engine::Coord;
+use engine::loader::{Pattern, from_file as load_from_file, from_reader as load_from_reader,
+                     to_string as save_to_string, to_file as save_to_file};
+use engine::{Engine, Grid, Rule, RuleTable, Topology};
 
 
 struct Game<'a> {
@@ -29,7 +34,8 @@ struct Game<'a> {
 impl<'a> Game<'a> {
 
     fn new(screen_width: f64, screen_height: f64,
-           board_cols: Option<usize>, board_rows: Option<usize>, initial_config: Option<Vec<Coord>>) -> Game<'a> {
+           grid: Grid, rule: Rule, threads: usize,
+           initial_config: Option<Pattern>) -> Game<'a> {
 
         let window: PistonWindow = WindowSettings::new(
             "Conway's Game of Life",
@@ -42,7 +48,7 @@ impl<'a> Game<'a> {
 
         let mut tmp = Game {
             ui_manager: ui::new(Rc::new(ui::GraphicsWindow::new(screen_width, screen_height, window)),
-                                Rc::new(RefCell::new(engine::Engine::new(None, board_cols, board_rows))),
+                                Rc::new(RefCell::new(engine::Engine::new(None, grid.cols, grid.rows, rule))),
                                 Rc::new(RefCell::new(ui::Resources {
                                     font: GlyphCache::new(Search::ParentsThenKids(3, 3).
                                     for_folder("assets").unwrap().
@@ -51,11 +57,15 @@ impl<'a> Game<'a> {
             ),
         };
 
+        tmp.ui_manager.get_engine().borrow_mut().set_threads(threads);
+        tmp.ui_manager.get_engine().borrow_mut().set_grid(grid);
+
         match initial_config {
             Some(parsed) => {
                 let mut engine_ref = tmp.ui_manager.get_engine();
-                let board = engine_ref.borrow().from_coord_vec(parsed.clone());
+                let board = engine_ref.borrow().from_pattern(&parsed);
                 engine_ref.borrow_mut().set_board(board);
+                engine_ref.borrow_mut().set_metadata(parsed.metadata);
             }
             None => {}
         }
@@ -69,6 +79,64 @@ impl<'a> Game<'a> {
 
 }
 
+fn run_headless(grid: Grid, rule: Rule, threads: usize,
+                pattern: Pattern, generations: u64, output: Option<String>, stop_on_period: bool) {
+
+    // simulate given number of generations without a window and
+    // report results, the final pattern is saved if output is set,
+    // simulation may stop earlier once the pattern becomes periodic
+
+    let mut engine = Engine::new(None, grid.cols, grid.rows, rule);
+    engine.set_threads(threads);
+    engine.set_grid(grid);
+    let board = engine.from_pattern(&pattern);
+    engine.set_board(board);
+    engine.set_metadata(pattern.metadata);
+
+    let time = if stop_on_period {
+        engine.iterations_until_periodic(generations)
+    } else {
+        engine.iterations(generations)
+    };
+
+    let bounding_box = match engine.get_board().get_bounding_box() {
+        Some((top_left, bottom_right)) => format!("({}, {}) - ({}, {})",
+                                                  top_left.col, top_left.row,
+                                                  bottom_right.col, bottom_right.row),
+        None => String::from("empty")
+    };
+
+    let mut report = format!("generations {}\npopulation {}\nbounding box {}\n",
+                             engine.cur_iteration(), engine.get_board().get_population(), bounding_box);
+
+    // periods are detected only when the simulation may stop on them
+    if stop_on_period {
+        match engine.get_periodicity() {
+            Some(periodicity) => report.push_str(&format!("periodicity {}\n", periodicity)),
+            None => report.push_str("periodicity not found\n")
+        }
+    }
+
+    report.push_str(&format!("time {:.*} s", 5, time));
+
+    match output {
+        Some(ref file_name) if file_name == "-" => {
+            // keep stdout clean for the pattern itself
+            print!("{}", save_to_string(engine.get_board(), engine.get_rule(), engine.get_metadata()));
+            eprintln!("{}", report);
+        },
+        Some(file_name) => {
+            println!("{}", report);
+            if let Err(err) = save_to_file(engine.get_board(), engine.get_rule(), engine.get_metadata(),
+                                           file_name.clone()) {
+                eprintln!("Couldn't save pattern to {}, reason {}", file_name, err);
+                process::exit(1);
+            }
+        },
+        None => println!("{}", report)
+    }
+}
+
 fn main() {
 
     let matches = App::new("Conway's Game of Life")
@@ -98,9 +166,48 @@ fn main() {
             .takes_value(true))
         .arg(Arg::with_name("file")
             .long("file")
-            .help("Read configuration from a file")
+            .help("Read configuration from a file in any supported format, possibly gzip-compressed, \
+                   use - for stdin")
             .value_name("FILE")
             .takes_value(true))
+        .arg(Arg::with_name("topology")
+            .long("topology")
+            .help("Sets board sizes and topology in Golly notation, e.g. T30,20 for a torus, \
+                   P30,20 for a plane, K30*,20 for a Klein bottle, C30,20 for a cross-surface \
+                   or S30 for a sphere, overrides --cols and --rows")
+            .value_name("TOPOLOGY")
+            .takes_value(true))
+        .arg(Arg::with_name("rule")
+            .long("rule")
+            .help("Sets rule in B/S notation, a name of a built-in rule table, e.g. WireWorld, \
+                   or a path to a rule table in Golly's .rule format, default is B3/S23")
+            .value_name("RULE")
+            .default_value("B3/S23")
+            .takes_value(true))
+        .arg(Arg::with_name("threads")
+            .long("threads")
+            .help("Sets number of threads used to compute generations, all cores are used if omitted")
+            .value_name("THREADS")
+            .takes_value(true))
+        .arg(Arg::with_name("headless")
+            .long("headless")
+            .help("Runs simulation without a window and prints final population, bounding box and timing")
+            .requires("file")
+            .requires("generations"))
+        .arg(Arg::with_name("generations")
+            .long("generations")
+            .help("Sets number of generations to simulate in headless mode")
+            .value_name("GENERATIONS")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .long("output")
+            .help("Saves final pattern in headless mode to a file, format is chosen by its extension: .rle, .cells, .lif (Life 1.06) or .mc, RLE is used by default, use - for stdout")
+            .value_name("OUTPUT")
+            .takes_value(true))
+        .arg(Arg::with_name("stop-on-period")
+            .long("stop-on-period")
+            .help("Stops headless simulation once the pattern becomes still, oscillating or a spaceship")
+            .requires("headless"))
 
         .get_matches();
 
@@ -110,14 +217,47 @@ fn main() {
     let scr_width = value_t_or_exit!(matches, "width", f64);
     let scr_height = value_t_or_exit!(matches, "height", f64);
 
+    let mut rule = match matches.value_of("rule") {
+        Some(rule_file) if rule_file.ends_with(".rule") => {
+            match fs::read_to_string(rule_file).map(|content| RuleTable::parse(&content)) {
+                Ok(Ok(table)) => Rule::from_table(table),
+                Ok(Err(err)) => {
+                    println!("Couldn't parse rule table {}, reason {}", rule_file, err);
+                    process::exit(1);
+                },
+                Err(err) => {
+                    println!("Couldn't read rule table {}, reason {}", rule_file, err);
+                    process::exit(1);
+                }
+            }
+        },
+        _ => value_t_or_exit!(matches, "rule", Rule)
+    };
+
+    // a wrong number of threads is an error, all cores are used only if it's omitted
+    let threads = if matches.is_present("threads") {
+        value_t_or_exit!(matches, "threads", usize)
+    } else {
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    };
+
     let file_name = value_t!(matches, "file", String).ok();
 
     let init_config = match file_name {
         Some(file_name) => {
-            match load_from_file(file_name.clone()) {
+            let loaded = if file_name == "-" {
+                load_from_reader(io::stdin())
+            } else {
+                load_from_file(file_name.clone())
+            };
+
+            match loaded {
                 Ok(parsed) => Some(parsed),
                 Err(err) => {
                     println!("Couldn't parse file {}, reason {}", file_name, err);
+                    if matches.is_present("headless") {
+                        process::exit(1);
+                    }
                     None
                 }
             }
@@ -125,7 +265,38 @@ fn main() {
         None => None
     };
 
-    let mut game = Game::new(scr_width, scr_height, board_cols, board_rows, init_config);
+    let mut grid = Grid::new(Topology::default(), board_cols, board_rows);
+
+    if matches.is_present("topology") {
+        grid = value_t_or_exit!(matches, "topology", Grid);
+    } else if !grid.is_bounded() {
+        // topology from the file header is used unless board sizes were set explicitly
+        if let Some(file_grid) = init_config.as_ref().and_then(|pattern| pattern.grid) {
+            grid = file_grid;
+        }
+    }
+
+    if matches.occurrences_of("rule") == 0 {
+        // rule from the file header is used unless it was set explicitly
+        if let Some(ref pattern) = init_config {
+            if let Some(ref file_rule) = pattern.rule {
+                rule = file_rule.clone();
+            }
+        }
+    }
+
+    if matches.is_present("headless") {
+        let generations = value_t_or_exit!(matches, "generations", u64);
+        let output = value_t!(matches, "output", String).ok();
+
+        let stop_on_period = matches.is_present("stop-on-period");
+
+        run_headless(grid, rule, threads, init_config.unwrap(), generations, output,
+                     stop_on_period);
+        return;
+    }
+
+    let mut game = Game::new(scr_width, scr_height, grid, rule, threads, init_config);
 
     game.event_dispatcher();
 }
@@ -11,11 +11,12 @@ use cam::Cam;
 use self::windows::{WindowBase, PostAction, States};
 use self::windows::board::GameBoard;
 use self::windows::hud::HUDWindow;
-use self::windows::confirm::{ConfirmationWindow, UserChoice};
 use self::windows::info::InfoWindow;
 
-use engine::Engine;
-use engine::loader::from_string as load_from_string;
+use engine::{Engine, Coord};
+use engine::transform::{Transform, StampMode};
+use engine::loader::{from_string as load_from_string, to_string as save_to_string,
+                     to_file as save_to_file, region_to_string};
 
 use opengl_graphics::GlGraphics;
 use opengl_graphics::glyph_cache::GlyphCache;
@@ -29,6 +30,10 @@ use clipboard::{ClipboardContext, ClipboardProvider};
 
 pub const OPENGL: piston_window::OpenGL = OpenGL::V3_2;
 
+// probability of a cell to be alive in randomly filled boards and selections
+const DEFAULT_FILL_DENSITY: f64 = 0.3;
+const FILL_DENSITY_STEP: f64 = 0.1;
+
 
 pub struct UI<'a> {
 
@@ -40,7 +45,16 @@ pub struct UI<'a> {
     engine: Rc<RefCell<Engine>>,
     resources: Rc<RefCell<Resources>>,
 
-    clipboard_ctx: ClipboardContext
+    clipboard_ctx: ClipboardContext,
+
+    // modifier keys are tracked here for all windows
+    modifiers: Rc<Cell<Modifiers>>,
+
+    // rectangle drawn on the board with the mouse
+    selection: Rc<Cell<Option<Selection>>>,
+
+    // shown in the HUD
+    fill_density: Rc<Cell<f64>>,
 }
 
 pub struct Resources {
@@ -54,6 +68,34 @@ pub struct GraphicsWindow {
     height: f64,
 }
 
+#[derive(Copy, Clone, Default)]
+pub struct Modifiers {
+    // ctrl for undo/redo and selection shortcuts, shift for drawing selections
+    pub ctrl: bool,
+    pub shift: bool,
+}
+
+#[derive(Copy, Clone)]
+pub struct Selection {
+    // cell where dragging started and the cell under the cursor
+    pub anchor: Coord,
+    pub corner: Coord,
+}
+
+impl Selection {
+
+    pub fn new(anchor: Coord, corner: Coord) -> Self {
+        Selection { anchor, corner }
+    }
+
+    pub fn get_bounds(&self) -> (Coord, Coord) {
+        // top left and bottom right corners, both are inside the selection
+        (Coord { col: self.anchor.col.min(self.corner.col), row: self.anchor.row.min(self.corner.row) },
+         Coord { col: self.anchor.col.max(self.corner.col), row: self.anchor.row.max(self.corner.row) })
+    }
+
+}
+
 pub struct CellProp {
     cell_width: f64,
     cell_height: f64,
@@ -150,17 +192,6 @@ impl<'a> UI<'a> {
         self.resources.clone()
     }
 
-    fn create_prompt_window<F: 'a>(&mut self, msg: &'a str, callback: F)  where
-        F: FnMut(Rc<RefCell<Engine>>, UserChoice) {
-
-        let confirm_window = Box::new(ConfirmationWindow::new(self.get_resources(), self.get_engine(),
-                                         callback, msg,
-                                         self.get_window().get_width(),
-                                         self.get_window().get_height()));
-
-        self.push_front(confirm_window);
-    }
-
     fn create_info_window(&mut self, msg: &'a str) {
 
         let info_window = Box::new(InfoWindow::new(
@@ -174,6 +205,78 @@ impl<'a> UI<'a> {
 
     }
 
+    fn copy_selection(&mut self) -> bool {
+
+        // copy live cells of the selection to clipboard in RLE format,
+        // returns false if nothing is selected or copying failed
+
+        let (top_left, bottom_right) = match self.selection.get() {
+            Some(selection) => selection.get_bounds(),
+            None => return false
+        };
+
+        let encoded = {
+            let engine = self.engine.borrow();
+            region_to_string(engine.get_board(), engine.get_rule(), top_left, bottom_right)
+        };
+
+        match self.clipboard_ctx.set_contents(encoded) {
+            Ok(_) => true,
+            Err(err) => {
+                println!("Couldn't copy selection to clipboard, reason {}", err);
+                false
+            }
+        }
+    }
+
+    fn paste_selection(&mut self) {
+
+        // place the pattern from clipboard at the top left corner of the
+        // selection adding it to the board, the selection then covers it
+
+        let top_left = match self.selection.get() {
+            Some(selection) => selection.get_bounds().0,
+            None => return
+        };
+
+        if let Ok(content) = self.clipboard_ctx.get_contents() {
+            match load_from_string(content) {
+                Ok(parsed) => {
+                    self.engine.borrow_mut().stamp(&parsed, top_left, StampMode::Or);
+
+                    let bottom_right = Coord { col: top_left.col + parsed.width.max(1) as isize - 1,
+                                               row: top_left.row + parsed.height.max(1) as isize - 1 };
+                    self.selection.set(Some(Selection::new(top_left, bottom_right)));
+                }
+                Err(err) => { println!("{}", err) }
+            }
+        }
+    }
+
+    fn set_modifier(&self, key: Key, pressed: bool) {
+        let mut modifiers = self.modifiers.get();
+
+        match key {
+            Key::LCtrl | Key::RCtrl => modifiers.ctrl = pressed,
+            Key::LShift | Key::RShift => modifiers.shift = pressed,
+            _ => {}
+        }
+
+        self.modifiers.set(modifiers);
+    }
+
+    fn transform_selection(&mut self, transform: Transform) {
+
+        // rotate or flip cells of the selection in place, the selection
+        // follows the transformed rectangle
+
+        if let Some(selection) = self.selection.get() {
+            let (top_left, bottom_right) = selection.get_bounds();
+            let (top_left, bottom_right) = self.engine.borrow_mut().transform_region(top_left, bottom_right, transform);
+            self.selection.set(Some(Selection::new(top_left, bottom_right)));
+        }
+    }
+
     fn manage_windows(&mut self, e: &Event) {
 
         let mut to_remove = Vec::new();
@@ -223,35 +326,85 @@ impl<'a> UI<'a> {
 
                             match some_event {
 
-                                &Event::Input(Input::Press(Button::Keyboard(Key::X))) => {
+                                // selection controls ->
+                                &Event::Input(Input::Press(Button::Keyboard(Key::C))) if self.modifiers.get().ctrl => {
+                                    // copy the selection
+                                    self.copy_selection();
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::X))) if self.modifiers.get().ctrl => {
+                                    // copy the selection and clear it, can be reverted with undo
+                                    if self.copy_selection() {
+                                        let (top_left, bottom_right) = self.selection.get().unwrap().get_bounds();
+                                        self.engine.borrow_mut().clear_region(top_left, bottom_right, true);
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::V))) if self.modifiers.get().ctrl => {
+                                    self.paste_selection();
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::Delete))) => {
+                                    // clear inside the selection or outside of it with ctrl,
+                                    // can be reverted with undo
+                                    if let Some(selection) = self.selection.get() {
+                                        let (top_left, bottom_right) = selection.get_bounds();
+                                        self.engine.borrow_mut().clear_region(top_left, bottom_right, !self.modifiers.get().ctrl);
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::R)))
+                                    if self.cur_state.get() == States::Paused && self.selection.get().is_some() => {
+                                    // fill the selection with a random pattern, can be reverted with undo
+                                    let (top_left, bottom_right) = self.selection.get().unwrap().get_bounds();
+                                    self.engine.borrow_mut().fill_random(top_left, bottom_right, self.fill_density.get());
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::LeftBracket))) => {
+                                    self.fill_density.set((self.fill_density.get() - FILL_DENSITY_STEP).max(0.0));
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::RightBracket))) => {
+                                    self.fill_density.set((self.fill_density.get() + FILL_DENSITY_STEP).min(1.0));
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::T))) if self.selection.get().is_some() => {
+                                    // rotate the selection clockwise or counterclockwise with ctrl
+                                    if self.modifiers.get().ctrl {
+                                        self.transform_selection(Transform::Rotate270);
+                                    } else {
+                                        self.transform_selection(Transform::Rotate90);
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::M))) if self.selection.get().is_some() => {
+                                    // mirror the selection swapping its left and right sides
+                                    self.transform_selection(Transform::FlipHorizontal);
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::V))) if self.selection.get().is_some() => {
+                                    // flip the selection upside down
+                                    self.transform_selection(Transform::FlipVertical);
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::D))) if self.selection.get().is_some() => {
+                                    // drop the selection
+                                    self.selection.set(None);
+                                }
+                                // selection controls <-
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::I))) => {
                                     match self.clipboard_ctx.get_contents() {
 
                                         Ok(content) => {
 
+                                            // format of the pattern is detected by its content
                                             match load_from_string(content) {
 
                                                 Ok(parsed) => {
-                                                    // setup new board config here
-                                                    let engine = self.get_engine();
-
-                                                    if engine.borrow().get_board().is_infinite() {
-                                                        self.create_info_window("Can't generate random \
-                                                        configuration for infinite board");
-
-                                                    } else {
-
-                                                        self.create_prompt_window(
-                                                            "Current position will be lost, ok?",
-                                                            move |engine, user_choice| {
-                                                                if user_choice == UserChoice::Ok {
-                                                                    // generate random board
-                                                                    let board = engine.borrow().from_coord_vec(parsed.clone());
-                                                                    engine.borrow_mut().set_board(board);
-                                                                }
-                                                            }
-                                                        );
-                                                    }
-
+                                                    // replace the board with the pasted pattern,
+                                                    // it can be reverted with undo
+                                                    self.engine.borrow_mut().load_pattern(&parsed);
                                                 }
                                                 Err(err) => { println!("{}", err) }
                                             }
@@ -260,20 +413,73 @@ impl<'a> UI<'a> {
                                     }
                                 }
 
-                                &Event::Input(Input::Press(Button::Keyboard(Key::C))) => {
+                                &Event::Input(Input::Press(Button::Keyboard(Key::E))) => {
+
+                                    // export current board to clipboard in RLE format
+
+                                    let encoded = {
+                                        let engine = self.engine.borrow();
+                                        save_to_string(engine.get_board(), engine.get_rule(), engine.get_metadata())
+                                    };
+
+                                    if let Err(err) = self.clipboard_ctx.set_contents(encoded) {
+                                        println!("Couldn't copy board to clipboard, reason {}", err);
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::W))) => {
 
-                                    // clear board and reset counters
+                                    // save current board to a file in RLE format
+
+                                    let engine = self.engine.borrow();
+                                    let file_name = format!("life_{}.rle", engine.cur_iteration());
+
+                                    match save_to_file(engine.get_board(), engine.get_rule(), engine.get_metadata(),
+                                                       file_name.clone()) {
+                                        Ok(_) => println!("Board saved to {}", file_name),
+                                        Err(err) => println!("Couldn't save board to {}, reason {}", file_name, err),
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::N))) => {
+
+                                    // clear board and reset counters, can be reverted with undo
 
                                     self.cur_state.set(States::Paused);
+                                    self.engine.borrow_mut().clear();
+                                }
 
-                                    self.create_prompt_window(
-                                        "Are you sure you want to clear the board?",
-                                        |engine, user_choice| {
-                                            if user_choice == UserChoice::Ok {
-                                                engine.borrow_mut().reset();
-                                            }
-                                        }
-                                    );
+                                &Event::Input(Input::Press(Button::Keyboard(key @ Key::LCtrl))) |
+                                &Event::Input(Input::Press(Button::Keyboard(key @ Key::RCtrl))) |
+                                &Event::Input(Input::Press(Button::Keyboard(key @ Key::LShift))) |
+                                &Event::Input(Input::Press(Button::Keyboard(key @ Key::RShift))) => {
+                                    self.set_modifier(key, true);
+                                }
+
+                                &Event::Input(Input::Release(Button::Keyboard(key @ Key::LCtrl))) |
+                                &Event::Input(Input::Release(Button::Keyboard(key @ Key::RCtrl))) |
+                                &Event::Input(Input::Release(Button::Keyboard(key @ Key::LShift))) |
+                                &Event::Input(Input::Release(Button::Keyboard(key @ Key::RShift))) => {
+                                    self.set_modifier(key, false);
+                                }
+
+                                &Event::Input(Input::Focus(false)) => {
+                                    // releases of modifiers aren't received by unfocused window
+                                    self.modifiers.set(Modifiers::default());
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::Z))) if self.modifiers.get().ctrl => {
+                                    // undo the last edit of the board
+                                    if !self.engine.borrow_mut().undo() {
+                                        println!("Nothing to undo");
+                                    }
+                                }
+
+                                &Event::Input(Input::Press(Button::Keyboard(Key::Y))) if self.modifiers.get().ctrl => {
+                                    // redo the last undone edit of the board
+                                    if !self.engine.borrow_mut().redo() {
+                                        println!("Nothing to redo");
+                                    }
                                 }
 
                                 &Event::Input(Input::Press(Button::Keyboard(Key::P))) => {
@@ -292,6 +498,17 @@ impl<'a> UI<'a> {
                                     }
                                 }
 
+                                &Event::Input(Input::Press(Button::Keyboard(Key::A))) => {
+                                    // step one generation back, simulation is paused
+                                    if self.cur_state.get() == States::Working || self.cur_state.get() == States::Paused {
+                                        self.cur_state.set(States::Paused);
+
+                                        if !self.engine.borrow_mut().step_back() {
+                                            println!("No more recorded generations");
+                                        }
+                                    }
+                                }
+
                                 &Event::Input(Input::Press(Button::Keyboard(Key::R))) => {
                                     if self.cur_state.get() == States::Paused {
 
@@ -301,15 +518,9 @@ impl<'a> UI<'a> {
                                             self.create_info_window("Can't generate random \
                                             configuration for infinite board");
                                         } else {
-                                            self.create_prompt_window(
-                                                "Current position will be lost, ok?",
-                                                |engine, user_choice| {
-                                                    if user_choice == UserChoice::Ok {
-                                                        // generate random board
-                                                        let board = engine.borrow().create_random(0.3);
-                                                        engine.borrow_mut().set_board(board);
-                                                    }
-                                                });
+                                            // generate random board, can be reverted with undo
+                                            let board = engine.borrow().create_random(self.fill_density.get());
+                                            engine.borrow_mut().replace_board(board);
                                         }
 
                                     }
@@ -360,14 +571,20 @@ pub fn new<'a>(window: Rc<GraphicsWindow>, engine: Rc<RefCell<Engine>>,
                       window: window,
                       engine: engine,
                       resources: resources,
-                      clipboard_ctx: ClipboardProvider::new().unwrap()
+                      clipboard_ctx: ClipboardProvider::new().unwrap(),
+                      modifiers: Rc::new(Cell::new(Modifiers::default())),
+                      selection: Rc::new(Cell::new(None)),
+                      fill_density: Rc::new(Cell::new(DEFAULT_FILL_DENSITY)),
                     };
 
     let board_window = Box::new(GameBoard::new(ui.get_window(),
-                                               ui.get_engine()));
+                                               ui.get_engine(),
+                                               ui.selection.clone(),
+                                               ui.modifiers.clone()));
 
     let hud_window = Box::new(HUDWindow::new(ui.get_resources(),
-                                             ui.get_engine()));
+                                             ui.get_engine(),
+                                             ui.fill_density.clone()));
 
     ui.push(board_window);
     ui.push(hud_window);
@@ -7,10 +7,10 @@ use piston_window::{Context, Event, Input, Button, Key,
 use super::{WindowBase, PostAction};
 use super::super::States;
 
-use super::super::{CellProp, GraphicsWindow};
+use super::super::{CellProp, GraphicsWindow, Modifiers, Selection};
 use super::Cam;
 
-use engine::{Engine, CellDesc};
+use engine::{Engine, CellDesc, Coord, Shape, Topology};
 
 use opengl_graphics::GlGraphics;
 
@@ -18,6 +18,13 @@ use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 use std::time::{Instant, Duration};
 
+// number of generations to skip ahead at once
+const JUMP_GENERATIONS: u64 = 1024;
+// number of generations that can be stepped back
+const REWIND_GENERATIONS: usize = 512;
+// selected cells are highlighted with a translucent fill
+const SELECTION_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 0.25];
+
 
 pub struct GameBoard {
 
@@ -33,11 +40,19 @@ pub struct GameBoard {
     last_iter_time: Instant,
     last_pos: Option<[f64; 2]>,
 
+    // dragging with shift draws a selection instead of cells
+    selection: Rc<Cell<Option<Selection>>>,
+    modifiers: Rc<Cell<Modifiers>>,
+    selecting: bool,
+
 }
 
 impl GameBoard {
 
-    pub fn new(window: Rc<GraphicsWindow>, engine: Rc<RefCell<Engine>>) -> GameBoard {
+    pub fn new(window: Rc<GraphicsWindow>, engine: Rc<RefCell<Engine>>,
+               selection: Rc<Cell<Option<Selection>>>, modifiers: Rc<Cell<Modifiers>>) -> GameBoard {
+
+        engine.borrow_mut().set_rewind_capacity(REWIND_GENERATIONS);
 
         GameBoard {
 
@@ -53,6 +68,10 @@ impl GameBoard {
             last_iter_time: Instant::now(),
             last_pos: None,
 
+            selection,
+            modifiers,
+            selecting: false,
+
         }
 
     }
@@ -114,15 +133,23 @@ impl WindowBase for GameBoard {
         if self.render {
             {
                 let engine = self.engine.borrow();
-
-                for CellDesc { coord, gen, is_alive, .. } in engine.get_board().iter() {
-                    if is_alive {
-                        let (x, y) = self.to_screen(coord.col, coord.row);
-                        rectangle(GameBoard::get_color(gen), [x, y,
-                            self.cell.get_width(&self.cam),
-                            self.cell.get_height(&self.cam)],
-                                  c.transform, g);
-                    }
+                let states = engine.get_rule().get_states();
+                let table = engine.get_rule().get_table();
+
+                for CellDesc { coord, gen, state, .. } in engine.get_board().iter() {
+                    let color = match table.and_then(|table| table.get_color(state)) {
+                        // rule tables may define colors of their states
+                        Some(rgb) if state > 0 => GameBoard::get_table_color(rgb),
+                        _ if state == 1 => GameBoard::get_color(gen),
+                        _ if state > 1 => GameBoard::get_dying_color(state, states),
+                        _ => continue
+                    };
+
+                    let (x, y) = self.to_screen(coord.col, coord.row);
+                    rectangle(color, [x, y,
+                        self.cell.get_width(&self.cam),
+                        self.cell.get_height(&self.cam)],
+                              c.transform, g);
                 }
             }
         }
@@ -132,6 +159,10 @@ impl WindowBase for GameBoard {
         }
 
         self.draw_borders(&c, g);
+
+        if let Some(selection) = self.selection.get() {
+            self.draw_selection(selection, &c, g);
+        }
     }
 
     fn event_dispatcher(&mut self, event: &Event, cur_state: &Cell<States>) -> PostAction {
@@ -167,13 +198,40 @@ impl WindowBase for GameBoard {
                 self.render = true;
             }
 
+            &Event::Input(Input::Press(Button::Keyboard(Key::A))) => {
+                // and when stepping back
+                self.render = true;
+            }
+
+            &Event::Input(Input::Press(Button::Keyboard(Key::J))) => {
+                // skip ahead using HashLife
+                self.engine.borrow_mut().jump(JUMP_GENERATIONS);
+            }
+
             &Event::Input(Input::Press(Button::Keyboard(Key::G))) => {
                 // show/hide grid
                 self.show_grid = !self.show_grid;
             }
 
             // mouse controls ->
+            &Event::Input(Input::Press(Button::Mouse(MouseButton::Left))) if self.modifiers.get().shift => {
+                // start a new selection at the cell under the cursor
+                if let Some(pos) = self.last_pos {
+                    let (col, row) = self.to_logical(pos[0], pos[1]);
+                    let cell = Coord { col, row };
+
+                    self.selection.set(Some(Selection::new(cell, cell)));
+                    self.selecting = true;
+                }
+            }
+
+            &Event::Input(Input::Release(Button::Mouse(MouseButton::Left))) if self.selecting => {
+                self.selecting = false;
+            }
+
             &Event::Input(Input::Press(Button::Mouse(MouseButton::Left))) => {
+                // everything drawn until the button is released is undone at once
+                self.engine.borrow_mut().begin_edit();
                 cur_state.set(States::Draw);
             }
 
@@ -184,10 +242,17 @@ impl WindowBase for GameBoard {
 
                     cur_state.set(States::Paused);
                 }
+                self.engine.borrow_mut().end_edit();
             }
 
             &Event::Input(Input::Move(Motion::MouseCursor(x, y))) => {
-                if cur_state.get() == States::Draw {
+                if self.selecting {
+                    // the opposite corner follows the cursor
+                    if let Some(selection) = self.selection.get() {
+                        let (col, row) = self.to_logical(x, y);
+                        self.selection.set(Some(Selection::new(selection.anchor, Coord { col, row })));
+                    }
+                } else if cur_state.get() == States::Draw {
                     self.born_or_kill(false, x, y);
                 }
                 self.last_pos = Some([x, y]);
@@ -336,29 +401,54 @@ impl GameBoard {
         (center_x, center_y)
     }
 
+    #[inline]
+    fn is_hex(&self) -> bool {
+        self.engine.borrow().get_rule().get_neighbourhood().shape == Shape::Hexagonal
+    }
+
+    #[inline]
+    fn get_hex_shift(&self, y: f64) -> f64 {
+        // cells of hexagonal rules are drawn with every row shifted by half
+        // a cell to the left relative to the row above, so all 6 neighbours
+        // of a cell touch it, returns the shift at the given screen coordinate
+        if !self.is_hex() {
+            return 0.0;
+        }
+
+        let row = (y - self.cam.translate_y(self.window.get_half_height())) /
+            self.cell.get_height(&self.cam);
+
+        -0.5 * row * self.cell.get_width(&self.cam)
+    }
+
     fn to_logical(&self, x: f64, y: f64) -> (isize, isize) {
         let (x, y) = self.cam.translate_inv(x, y);
 
-        let mut offset_x = x - self.window.get_half_width();
         let mut offset_y = y - self.window.get_half_height();
 
         // TODO: Ensure this needed
 
-        if offset_x < 0.0 {
-            offset_x -= self.cell.get_half_width(&self.cam);
-        } else if offset_x > 0.0 {
-            offset_x += self.cell.get_half_width(&self.cam);
-        }
-
         if offset_y < 0.0 {
             offset_y -= self.cell.get_half_height(&self.cam);
         } else if offset_y > 0.0 {
             offset_y += self.cell.get_half_height(&self.cam);
         }
 
-        let col = (offset_x / self.cell.get_width(&self.cam)) as isize;
         let row = (offset_y / self.cell.get_height(&self.cam)) as isize;
 
+        // rows of hexagonal rules are shifted
+        let shift = if self.is_hex() { -0.5 * row as f64 * self.cell.get_width(&self.cam) } else { 0.0 };
+
+        let mut offset_x = x - self.window.get_half_width() - shift;
+
+        if offset_x < 0.0 {
+            offset_x -= self.cell.get_half_width(&self.cam);
+        } else if offset_x > 0.0 {
+            offset_x += self.cell.get_half_width(&self.cam);
+        }
+
+        let col = (offset_x / self.cell.get_width(&self.cam)) as isize;
+
         (col, row)
     }
 
@@ -380,20 +470,18 @@ impl GameBoard {
         let y = row as f64 * self.cell.get_height(&self.cam) + self.window.get_half_height() -
             self.cell.get_half_height(&self.cam);
 
-        self.cam.translate(x, y)
+        let (x, y) = self.cam.translate(x, y);
+
+        (x + self.get_hex_shift(y + self.cell.get_half_height(&self.cam)), y)
     }
 
     fn born_or_kill(&mut self, kill_alive: bool, x: f64, y: f64) {
         let (col, row) = self.to_logical(x, y);
         let mut engine = self.engine.borrow_mut();
 
-        let board = engine.get_board_mut();
-
-        if kill_alive && board.is_alive(col, row) {
-            board.kill_at(col, row);
-        } else {
-            board.born_at(col, row);
-        }
+        // changes are recorded, so they can be undone
+        let kill = kill_alive && engine.get_board().is_alive(col, row);
+        engine.set_cell(col, row, !kill);
     }
 
     fn get_color(gen: usize) -> [f32; 4] {
@@ -401,6 +489,48 @@ impl GameBoard {
         [r as f32, 1.0 - r as f32, 0.0, 0.5]
     }
 
+    fn get_table_color(rgb: [u8; 3]) -> [f32; 4] {
+        [rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0, 0.5]
+    }
+
+    fn get_dying_color(state: usize, states: usize) -> [f32; 4] {
+        // dying cells of Generations rules fade out from blue to black
+        let fade = 1.0 - (state - 1) as f32 / (states - 1) as f32;
+        [0.0, 0.3 * fade, fade, 0.5]
+    }
+
+    fn draw_selection(&self, selection: Selection, c: &Context, g: &mut GlGraphics) {
+
+        // rows of hexagonal rules are shifted, so the selection is filled
+        // row by row, only rows visible in the window are drawn
+
+        let (top_left, bottom_right) = selection.get_bounds();
+
+        let width = (bottom_right.col - top_left.col + 1) as f64 * self.cell.get_width(&self.cam);
+        let height = self.cell.get_height(&self.cam);
+
+        let (_, first_visible) = self.to_logical(0.0, 0.0);
+        let (_, last_visible) = self.to_logical(0.0, self.window.get_height());
+
+        for row in top_left.row.max(first_visible - 1)..bottom_right.row.min(last_visible + 1) + 1 {
+            let (x, y) = self.to_screen(top_left.col, row);
+            rectangle(SELECTION_COLOR, [x, y, width, height], c.transform, g);
+        }
+
+        if self.is_hex() {
+            return;
+        }
+
+        // outline of the whole rectangle
+        let (left, top) = self.to_screen(top_left.col, top_left.row);
+        let (right, bottom) = (left + width, top + (bottom_right.row - top_left.row + 1) as f64 * height);
+
+        for edge in [[left, top, right, top], [right, top, right, bottom],
+                     [right, bottom, left, bottom], [left, bottom, left, top]].iter() {
+            line(super::WHITE, 0.3, *edge, c.transform, g);
+        }
+    }
+
     fn draw_borders(&self, c: &Context, g: &mut GlGraphics) {
 
         // draw borders
@@ -410,35 +540,62 @@ impl GameBoard {
         let top_offset_y = self.get_top_border();
         let bottom_offset_y = self.get_bottom_border();
 
+        let (left, right, top, bottom) = self.get_border_colors();
+
+        // boards of hexagonal rules are skewed, shifts of the first and the last rows
+        let top_shift = self.get_hex_shift(top_offset_y + self.cell.get_half_height(&self.cam));
+        let bottom_shift = self.get_hex_shift(bottom_offset_y - self.cell.get_half_height(&self.cam));
+
         if let Some(_) = self.engine.borrow_mut().get_board().get_cols() {
             // draw right border
 
-            line(super::RED, 0.3,
-                 [right_offset_x, top_offset_y, right_offset_x, bottom_offset_y],
+            line(right, 0.3,
+                 [right_offset_x + top_shift, top_offset_y, right_offset_x + bottom_shift, bottom_offset_y],
                  c.transform, g);
 
             // draw left border
 
-            line(super::RED, 0.3,
-                 [left_offset_x, top_offset_y, left_offset_x, bottom_offset_y],
+            line(left, 0.3,
+                 [left_offset_x + top_shift, top_offset_y, left_offset_x + bottom_shift, bottom_offset_y],
                  c.transform, g);
         }
 
         if let Some(_) = self.engine.borrow_mut().get_board().get_rows() {
             // draw top border
 
-            line(super::RED, 0.3,
-                 [left_offset_x, top_offset_y, right_offset_x, top_offset_y],
+            line(top, 0.3,
+                 [left_offset_x + top_shift, top_offset_y, right_offset_x + top_shift, top_offset_y],
                  c.transform, g);
 
             // draw bottom border
 
-            line(super::RED, 0.3,
-                 [left_offset_x, bottom_offset_y, right_offset_x, bottom_offset_y],
+            line(bottom, 0.3,
+                 [left_offset_x + bottom_shift, bottom_offset_y, right_offset_x + bottom_shift, bottom_offset_y],
                  c.transform, g);
         }
    }
 
+   fn get_border_colors(&self) -> ([f32; 4], [f32; 4], [f32; 4], [f32; 4]) {
+
+       // colors of left, right, top and bottom borders show how they are joined:
+       // gray borders are walls, red ones are joined as is, green ones with
+       // a shift, blue ones are flipped, edges of the same color are joined
+       // on a sphere
+
+       match self.engine.borrow().get_board().get_grid().topology {
+           Topology::Plane => (super::GRAY, super::GRAY, super::GRAY, super::GRAY),
+           Topology::Torus { col_shift, row_shift } => {
+               let cols_color = if row_shift != 0 { super::GREEN } else { super::RED };
+               let rows_color = if col_shift != 0 { super::GREEN } else { super::RED };
+               (cols_color, cols_color, rows_color, rows_color)
+           },
+           Topology::KleinBottle { twisted_rows: true } => (super::RED, super::RED, super::BLUE, super::BLUE),
+           Topology::KleinBottle { twisted_rows: false } => (super::BLUE, super::BLUE, super::RED, super::RED),
+           Topology::CrossSurface => (super::BLUE, super::BLUE, super::BLUE, super::BLUE),
+           Topology::Sphere => (super::RED, super::WHITE, super::RED, super::WHITE),
+       }
+   }
+
    fn draw_grid(&self, c: &Context, g: &mut GlGraphics) {
 
        let right_offset_x = self.get_right_border();
@@ -447,6 +604,11 @@ impl GameBoard {
        let top_offset_y = self.get_top_border();
        let bottom_offset_y = self.get_bottom_border();
 
+       if self.is_hex() {
+           self.draw_hex_grid(c, g, left_offset_x, right_offset_x, top_offset_y, bottom_offset_y);
+           return;
+       }
+
        let mut y = top_offset_y;
 
        // horizontal lines
@@ -472,4 +634,51 @@ impl GameBoard {
        }
    }
 
+   fn draw_hex_grid(&self, c: &Context, g: &mut GlGraphics,
+                    left_offset_x: f64, right_offset_x: f64, top_offset_y: f64, bottom_offset_y: f64) {
+
+       // every row of a hexagonal rule is shifted, so vertical lines
+       // are drawn row by row
+
+       let width = self.cell.get_width(&self.cam);
+       let height = self.cell.get_height(&self.cam);
+
+       let infinite = self.engine.borrow().get_board().get_cols().is_none();
+
+       let mut y = top_offset_y;
+
+       while y < bottom_offset_y {
+
+           let shift = self.get_hex_shift(y + 0.5 * height);
+
+           // rows of an infinite board cover the whole window
+           let (first_x, last_x) = if infinite {
+               let first_x = left_offset_x + shift;
+               (first_x - (first_x / width).ceil() * width, self.window.get_width())
+           } else {
+               (left_offset_x + shift, right_offset_x + shift)
+           };
+
+           // line between rows covers both of them
+           let line_shift = self.get_hex_shift(y);
+           line(super::GRAY, 0.09,
+                [first_x.min(left_offset_x + line_shift - 0.25 * width), y,
+                 last_x.max(right_offset_x + line_shift + 0.25 * width), y],
+                c.transform, g);
+
+           let mut x = first_x;
+
+           while x <= last_x {
+
+               line(super::GRAY, 0.09,
+                    [x, y, x, (y + height).min(bottom_offset_y)],
+                    c.transform, g);
+
+               x += width;
+           }
+
+           y += height;
+       }
+   }
+
 }
@@ -14,19 +14,29 @@ use opengl_graphics::GlGraphics;
 use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 
+// comments of a pattern may be long, only the first lines are shown
+const MAX_COMMENT_LINES: usize = 8;
+
 
 pub struct HUDWindow {
     engine: Rc<RefCell<Engine>>,
-    resources: Rc<RefCell<Resources>>
+    resources: Rc<RefCell<Resources>>,
+    // density of random fills changed by the UI
+    fill_density: Rc<Cell<f64>>,
     //state: isize,
 }
 
 impl HUDWindow {
-    pub fn new(resources: Rc<RefCell<Resources>>, engine: Rc<RefCell<Engine>>) -> HUDWindow {
+    pub fn new(resources: Rc<RefCell<Resources>>, engine: Rc<RefCell<Engine>>,
+               fill_density: Rc<Cell<f64>>) -> HUDWindow {
+
+        // periodicity of the pattern is shown
+        engine.borrow_mut().set_period_detection(true);
 
         HUDWindow {
             resources: resources,
-            engine: engine
+            engine: engine,
+            fill_density,
         }
 
     }
@@ -51,6 +61,38 @@ impl WindowBase for HUDWindow {
              &mut self.resources.borrow_mut().font,
              c.trans(320.0, 20.0).transform, g);
 
+        text(super::GREEN, 15,
+             &format!("fill density {:.0}%", 100.0 * self.fill_density.get()),
+             &mut self.resources.borrow_mut().font,
+             c.trans(500.0, 20.0).transform, g);
+
+        if let Some(periodicity) = self.engine.borrow().get_periodicity() {
+            text(super::GREEN, 15,
+                 &format!("{}", periodicity),
+                 &mut self.resources.borrow_mut().font,
+                 c.trans(660.0, 20.0).transform, g);
+        }
+
+        // name, author and comments of the loaded pattern
+        let engine = self.engine.borrow();
+        let metadata = engine.get_metadata();
+
+        let title = match (metadata.name.as_ref(), metadata.author.as_ref()) {
+            (Some(name), Some(author)) => Some(format!("{} by {}", name, author)),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some(author)) => Some(format!("by {}", author)),
+            (None, None) => None,
+        };
+
+        let comments = metadata.comments.iter().take(MAX_COMMENT_LINES);
+
+        for (idx, line) in title.iter().chain(comments).enumerate() {
+            text(super::GREEN, 12,
+                 line,
+                 &mut self.resources.borrow_mut().font,
+                 c.trans(10.0, 40.0 + 16.0 * idx as f64).transform, g);
+        }
+
     }
 
     fn event_dispatcher(&mut self, _event: &Event, _cur_state: &Cell<States>) -> PostAction {
@@ -1,4 +1,3 @@
-pub mod confirm;
 pub mod board;
 pub mod hud;
 pub mod info;